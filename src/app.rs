@@ -1,7 +1,25 @@
-use crate::chip8::Chip8;
+use crate::audio_device;
 use crate::ui; 
+use chip8_emulator::audio::{AudioGenerator, AudioOutput, DEFAULT_SAMPLE_RATE, NullOutput, WavOutput};
+use chip8_emulator::breakpoints::Breakpoints;
+use chip8_emulator::chip8::{AccessKind, Chip8};
+use chip8_emulator::clock::Clock;
+use chip8_emulator::coverage::{self, Coverage};
+use chip8_emulator::error::Chip8Error;
+use chip8_emulator::journal::DEFAULT_JOURNAL_CAPACITY;
+use chip8_emulator::listing::{Gutter, RomListing, Syntax};
+use chip8_emulator::octo;
+use chip8_emulator::profiler::Profiler;
+use chip8_emulator::quirks::QuirksPreset;
+use chip8_emulator::rewind::RewindBuffer;
+use chip8_emulator::symbols::SymbolTable;
+use chip8_emulator::trace::{self, TraceMode, Tracer};
 use eframe::egui;
-use std::time::Duration;
+use std::collections::BTreeSet;
+use std::fs::{self, File};
+use std::io::{BufWriter, Write};
+use std::path::{Path, PathBuf};
+use std::time::{Duration, Instant};
 
 #[derive(Debug, PartialEq, Eq)]
 pub enum ExecutionState {
@@ -9,59 +27,540 @@ pub enum ExecutionState {
     Paused,
 }
 
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum WatchType {
+    Read,
+    #[default]
+    Write,
+    Access,
+    Change,
+    Condition,
+    Opcode,
+}
+
+impl WatchType {
+    pub const ALL: [WatchType; 6] = [
+        WatchType::Read,
+        WatchType::Write,
+        WatchType::Access,
+        WatchType::Change,
+        WatchType::Condition,
+        WatchType::Opcode,
+    ];
+
+    pub fn name(self) -> &'static str {
+        match self {
+            WatchType::Read => "Read",
+            WatchType::Write => "Write",
+            WatchType::Access => "Access",
+            WatchType::Change => "Change",
+            WatchType::Condition => "Condition",
+            WatchType::Opcode => "Opcode",
+        }
+    }
+
+    pub fn hint(self) -> &'static str {
+        match self {
+            WatchType::Read | WatchType::Write | WatchType::Access => "0x300-0x302",
+            WatchType::Change => "V3",
+            WatchType::Condition => "V3 == 0x10 && I > 0x300",
+            WatchType::Opcode => "DXYN",
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum RegisterField {
+    V(usize),
+    I,
+    Pc,
+    Sp,
+    DelayTimer,
+    SoundTimer,
+    Stack(usize),
+}
+
+#[derive(Default)]
+pub struct DebuggerState {
+    pub breakpoints: Breakpoints,
+    pub new_breakpoint: String,
+    pub watch_type: WatchType,
+    pub new_watchpoint: String,
+    pub watch_error: Option<String>,
+    pub last_break: Option<String>,
+    pub editing: Option<(RegisterField, String)>,
+    pub edit_error: Option<String>,
+    pub symbols: SymbolTable,
+    pub listing: Option<RomListing>,
+    pub listing_rows: Vec<(Gutter, String)>,
+    listing_entry_points: BTreeSet<u16>,
+}
+
+impl DebuggerState {
+    pub fn rebuild_listing(&mut self, rom: &[u8]) {
+        let mut listing = RomListing::analyze_with_entry_points(rom, self.listing_entry_points.iter().copied());
+        listing.apply_symbols(&self.symbols);
+        self.listing_rows = listing.rows(Syntax::Cowgod);
+        self.listing = Some(listing);
+    }
+
+    pub fn clear_listing(&mut self) {
+        self.listing = None;
+        self.listing_rows.clear();
+        self.listing_entry_points.clear();
+    }
+
+    pub fn follow_pc(&mut self, pc: u16) {
+        let Some(listing) = &self.listing else {
+            return;
+        };
+        if listing.contains(pc) && !listing.is_code(pc) && self.listing_entry_points.insert(pc) {
+            let rom = listing.rom().to_vec();
+            self.rebuild_listing(&rom);
+        }
+    }
+}
+
+pub struct TraceSettings {
+    pub ring: bool,
+    pub ring_size: usize,
+    pub ranges: String,
+}
+
+impl Default for TraceSettings {
+    fn default() -> Self {
+        TraceSettings { ring: false, ring_size: 10_000, ranges: String::new() }
+    }
+}
+
+#[derive(Default)]
+pub struct ProfilerViewState {
+    pub open: bool,
+    pub recording: bool,
+}
+
+pub const RECENT_WRITE_FRAMES: u8 = 30;
+
+#[derive(Default)]
+pub struct MemoryViewState {
+    pub open: bool,
+    pub goto: String,
+    pub scroll_to: Option<usize>,
+    pub editing: Option<(usize, String)>,
+    pub edit_error: Option<String>,
+    pub recent_writes: Vec<u8>,
+}
+
+impl MemoryViewState {
+    fn note_writes(&mut self, chip8: &Chip8) {
+        let Some(cycle) = chip8.last_cycle.as_ref() else {
+            return;
+        };
+        self.recent_writes.resize(chip8.memory.len(), 0);
+        for access in cycle.accesses.iter().filter(|access| access.kind == AccessKind::Write) {
+            self.recent_writes[access.address] = RECENT_WRITE_FRAMES;
+        }
+    }
+
+    fn age_writes(&mut self) {
+        for age in self.recent_writes.iter_mut() {
+            *age = age.saturating_sub(1);
+        }
+    }
+}
+
 pub struct MyApp {
     chip8: Chip8,
-    cycles_per_frame: usize,
+    pub clock: Clock,
     pub(crate) rom_to_load: Option<String>, 
+    current_rom: Option<String>,
+    rom_image: Vec<u8>,
+    symbol_file: Option<PathBuf>,
     pub debugger_open: bool,
+    pub debugger: DebuggerState,
+    pub memory_view: MemoryViewState,
+    pub profiler: Profiler,
+    pub profiler_view: ProfilerViewState,
     pub execution_state: ExecutionState,
     pub step_requested: bool,
+    pub step_back_requested: bool,
+    pub reverse_continue_requested: bool,
+    pub rewind: RewindBuffer,
+    pub rewind_held: bool,
+    pub quirks_preset: QuirksPreset,
+    pub fault: Option<String>,
+    pub save_slot: u8,
+    pub trace_settings: TraceSettings,
+    pub coverage_enabled: bool,
+    tracer: Option<(Tracer, PathBuf)>,
+    audio_output: Box<dyn AudioOutput>,
+    audio_recording: Option<WavOutput>,
+    pub(crate) audio_generator: AudioGenerator,
+    last_update: Instant,
 }
 
 impl MyApp {
     pub fn new(rom_path: Option<&str>) -> Self {
-        let mut chip8 = Chip8::new();
-        if let Some(path) = rom_path {
-            chip8.chip8_load_rom(path);
-        }
-        Self {
-            chip8,
-            cycles_per_frame: 10,
-            rom_to_load: None,
+        let mut app = Self {
+            chip8: Chip8::new(),
+            clock: Clock::default(),
+            rom_to_load: rom_path.map(str::to_string),
+            current_rom: None,
+            rom_image: Vec::new(),
+            symbol_file: None,
             debugger_open: false,
+            debugger: DebuggerState::default(),
+            memory_view: MemoryViewState::default(),
+            profiler: Profiler::new(),
+            profiler_view: ProfilerViewState::default(),
             execution_state: ExecutionState::Running,
             step_requested: false,
+            step_back_requested: false,
+            reverse_continue_requested: false,
+            rewind: RewindBuffer::default(),
+            rewind_held: false,
+            quirks_preset: QuirksPreset::default(),
+            fault: None,
+            save_slot: 1,
+            trace_settings: TraceSettings::default(),
+            coverage_enabled: false,
+            tracer: None,
+            audio_output: audio_device::open_default_output()
+                .unwrap_or_else(|| Box::new(NullOutput::new(DEFAULT_SAMPLE_RATE))),
+            audio_recording: None,
+            audio_generator: AudioGenerator::new(),
+            last_update: Instant::now(),
+        };
+        app.reset();
+        app
+    }
+
+    pub fn is_recording_audio(&self) -> bool {
+        self.audio_recording.is_some()
+    }
+
+    pub fn start_audio_recording(&mut self, path: &Path) {
+        match WavOutput::create(path, self.audio_output.sample_rate()) {
+            Ok(recording) => self.audio_recording = Some(recording),
+            Err(err) => self.fault = Some(format!("Cannot start audio recording: {err}")),
+        }
+    }
+
+    pub fn stop_audio_recording(&mut self) {
+        if let Some(mut recording) = self.audio_recording.take()
+            && let Err(err) = recording.finish()
+        {
+            self.fault = Some(format!("Cannot finish audio recording: {err}"));
+        }
+    }
+
+    pub fn is_tracing(&self) -> bool {
+        self.tracer.is_some()
+    }
+
+    pub fn start_trace(&mut self, path: &Path) {
+        let Some(ranges) = trace::parse_ranges(&self.trace_settings.ranges) else {
+            self.fault = Some(format!("Invalid trace address ranges '{}'", self.trace_settings.ranges));
+            return;
+        };
+        match File::create(path) {
+            Ok(file) => {
+                let mode = if self.trace_settings.ring {
+                    TraceMode::Ring(self.trace_settings.ring_size)
+                } else {
+                    TraceMode::Stream
+                };
+                let mut tracer = Tracer::new(Box::new(BufWriter::new(file)), mode);
+                tracer.set_ranges(ranges);
+                self.tracer = Some((tracer, path.to_path_buf()));
+            }
+            Err(err) => self.fault = Some(format!("Cannot create trace '{}': {err}", path.display())),
+        }
+    }
+
+    pub fn stop_trace(&mut self) {
+        if let Some((tracer, path)) = self.tracer.take()
+            && let Err(err) = tracer.finish()
+        {
+            self.fault = Some(format!("Cannot write trace '{}': {err}", path.display()));
+        }
+    }
+
+    pub fn set_coverage(&mut self, enabled: bool) {
+        self.coverage_enabled = enabled;
+        if !enabled {
+            self.chip8.coverage = None;
+        } else if self.chip8.coverage.is_none() {
+            self.chip8.coverage = Some(Coverage::new());
+        }
+    }
+
+    pub fn export_coverage(&mut self, path: &Path) {
+        let Some(coverage) = self.chip8.coverage.as_ref() else {
+            self.fault = Some("Enable coverage tracking before exporting it".to_string());
+            return;
+        };
+        let lcov_path = coverage::lcov_path(path);
+        let symbols = &self.debugger.symbols;
+        let write = |path: &Path, report: &dyn Fn(&mut dyn Write) -> std::io::Result<()>| {
+            File::create(path)
+                .and_then(|file| {
+                    let mut out = BufWriter::new(file);
+                    report(&mut out)?;
+                    out.flush()
+                })
+                .map_err(|err| format!("Cannot write coverage '{}': {err}", path.display()))
+        };
+        let source = path.display().to_string();
+        let result = write(path, &|out| coverage.write_listing(out, &self.rom_image, symbols))
+            .and_then(|()| write(&lcov_path, &|out| coverage.write_lcov(out, &self.rom_image, symbols, &source)));
+        if let Err(err) = result {
+            self.fault = Some(err);
+        }
+    }
+
+    pub fn export_profile(&mut self, path: &Path, folded: bool) {
+        let result = File::create(path).and_then(|file| {
+            let mut out = BufWriter::new(file);
+            if folded {
+                self.profiler.write_folded(&mut out, &self.debugger.symbols)?;
+            } else {
+                self.profiler.write_csv(&mut out, &self.debugger.symbols)?;
+            }
+            out.flush()
+        });
+        if let Err(err) = result {
+            self.fault = Some(format!("Cannot write profile '{}': {err}", path.display()));
+        }
+    }
+
+    fn emulate_cycle(&mut self) -> Result<(), Chip8Error> {
+        if let Some((tracer, _)) = self.tracer.as_mut() {
+            tracer.record(&self.chip8);
+        }
+        if self.profiler_view.recording {
+            self.profiler.record(&self.chip8);
+        }
+        self.chip8.emulate_cycle()
+    }
+
+    fn save_slot_path(&self) -> Option<String> {
+        let rom = self.current_rom.as_ref()?;
+        Some(format!("{rom}.state{}", self.save_slot))
+    }
+
+    pub fn quick_save(&mut self) {
+        let Some(path) = self.save_slot_path() else {
+            self.fault = Some("Load a ROM before saving state".to_string());
+            return;
+        };
+        if let Err(err) = fs::write(&path, self.chip8.save_state()) {
+            self.fault = Some(format!("Cannot write save state '{path}': {err}"));
+        }
+    }
+
+    pub fn quick_load(&mut self) {
+        let Some(path) = self.save_slot_path() else {
+            self.fault = Some("Load a ROM before loading state".to_string());
+            return;
+        };
+        let result = fs::read(&path)
+            .map_err(|err| err.to_string())
+            .and_then(|data| self.chip8.load_state(&data).map_err(|err| err.to_string()));
+        if let Err(err) = result {
+            self.fault = Some(format!("Cannot load save state '{path}': {err}"));
+        }
+    }
+
+    fn report_fault(&mut self, err: Chip8Error) {
+        let mut message = err.to_string();
+        if let Some((tracer, path)) = self.tracer.take() {
+            match tracer.finish() {
+                Ok(()) => message.push_str(&format!("\n\nTrace written to '{}'", path.display())),
+                Err(err) => message.push_str(&format!("\n\nCannot write trace '{}': {err}", path.display())),
+            }
+        }
+        self.fault = Some(message);
+        self.execution_state = ExecutionState::Paused;
+    }
+
+    pub fn load_symbols(&mut self, path: &Path) {
+        match Self::read_symbols(path) {
+            Ok(symbols) => {
+                self.symbol_file = Some(path.to_path_buf());
+                self.debugger.symbols.extend(symbols);
+                if self.debugger.listing.is_some() {
+                    self.debugger.rebuild_listing(&self.rom_image);
+                }
+            }
+            Err(err) => self.fault = Some(err),
+        }
+    }
+
+    fn read_symbols(path: &Path) -> Result<SymbolTable, String> {
+        let text = fs::read_to_string(path).map_err(|err| format!("Cannot read symbols '{}': {err}", path.display()))?;
+        SymbolTable::parse(&text).map_err(|err| format!("Cannot load symbols '{}': {err}", path.display()))
+    }
+
+    fn reset(&mut self) {
+        if let Some(path) = self.rom_to_load.take() {
+            let symbol_file = PathBuf::from(format!("{path}.sym"));
+            self.symbol_file = symbol_file.exists().then_some(symbol_file);
+            self.current_rom = Some(path);
+            self.debugger.clear_listing();
+        }
+
+        let mut new_chip8 = Chip8::with_memory_size(self.quirks_preset.memory_size());
+        new_chip8.quirks = self.quirks_preset.quirks();
+        new_chip8.journal.set_capacity(DEFAULT_JOURNAL_CAPACITY);
+        self.debugger.symbols = SymbolTable::new();
+        if let Some(path) = self.current_rom.clone() {
+            let loaded = if octo::is_octo_source(&path) {
+                new_chip8.load_octo(&path).map(|program| {
+                    self.debugger.symbols = program.labels.into();
+                    program.rom
+                })
+            } else {
+                fs::read(&path)
+                    .map_err(|source| Chip8Error::RomRead { path: path.clone(), source })
+                    .and_then(|rom| new_chip8.load_rom_bytes(&rom).map(|()| rom))
+            };
+            match loaded {
+                Ok(rom) => self.rom_image = rom,
+                Err(err) => {
+                    self.current_rom = None;
+                    self.report_fault(err);
+                }
+            }
+        }
+        if self.coverage_enabled {
+            new_chip8.coverage = Some(Coverage::new());
+        }
+        if let Some(path) = self.symbol_file.clone() {
+            match Self::read_symbols(&path) {
+                Ok(symbols) => self.debugger.symbols.extend(symbols),
+                Err(err) => self.fault = Some(err),
+            }
+        }
+        if self.current_rom.is_some() {
+            self.debugger.rebuild_listing(&self.rom_image);
+        } else {
+            self.debugger.clear_listing();
+        }
+        self.chip8 = new_chip8;
+        self.rewind.clear();
+        self.rewind.push(&self.chip8);
+    }
+
+    pub fn can_step_back(&self) -> bool {
+        !self.chip8.journal.is_empty() || self.rewind.len() > 1
+    }
+
+    fn rewind_frame(&mut self) {
+        if let Some(mut previous) = self.rewind.rewind() {
+            previous.coverage = self.chip8.coverage.take();
+            previous.journal.set_capacity(self.chip8.journal.capacity());
+            self.chip8 = previous;
+        }
+    }
+
+    fn step_back(&mut self) {
+        if !self.chip8.step_back() {
+            self.rewind_frame();
+        }
+    }
+
+    fn reverse_continue(&mut self) {
+        while self.chip8.step_back() {
+            if self.debugger.breakpoints.hit(&self.chip8) {
+                break;
+            }
         }
     }
 }
 
 impl eframe::App for MyApp {
     fn update(&mut self, ctx: &egui::Context, _frame: &mut eframe::Frame) {
-        if let Some(path) = self.rom_to_load.take() {
-            let mut new_chip8 = Chip8::new();
-            new_chip8.chip8_load_rom(&path);
-            self.chip8 = new_chip8;
+        if self.rom_to_load.is_some() || self.chip8.memory.len() != self.quirks_preset.memory_size() {
+            self.reset();
         }
+        self.chip8.quirks = self.quirks_preset.quirks();
 
         ui::draw_menu_bar(self, ctx);
         if self.debugger_open {
-            ui::draw_debugger_panel(&self.chip8, ctx);
+            let editable = self.execution_state == ExecutionState::Paused;
+            ui::draw_debugger_panel(&mut self.chip8, &mut self.debugger, editable, ctx);
+        }
+        if self.memory_view.open {
+            let editable = self.execution_state == ExecutionState::Paused;
+            ui::draw_memory_panel(&mut self.chip8, &mut self.memory_view, editable, ctx);
+        }
+        if self.profiler_view.open {
+            ui::draw_profiler_panel(self, ctx);
         }
         ui::draw_emulator_screen(&self.chip8, ctx);
+        ui::draw_fault_dialog(self, ctx);
 
         if self.step_requested && self.execution_state == ExecutionState::Paused {
-            self.chip8.chip8_emulate_cycle();
+            self.chip8.vblank = true;
+            if let Err(err) = self.emulate_cycle() {
+                self.report_fault(err);
+            }
+            self.debugger.last_break = self.debugger.breakpoints.check(&self.chip8);
+            self.memory_view.note_writes(&self.chip8);
             self.step_requested = false;
         }
 
+        if self.step_back_requested && self.execution_state == ExecutionState::Paused {
+            self.step_back();
+            self.step_back_requested = false;
+        }
+
+        if self.reverse_continue_requested && self.execution_state == ExecutionState::Paused {
+            self.reverse_continue();
+            self.reverse_continue_requested = false;
+        }
+
+        let now = Instant::now();
+        let elapsed = now - self.last_update;
+        self.last_update = now;
+
         if self.execution_state == ExecutionState::Running {
-            for _ in 0..self.cycles_per_frame {
-                self.chip8.chip8_emulate_cycle();
+            let rewinding = self.rewind_held || ctx.input(|i| i.key_down(egui::Key::Backspace));
+            self.clock.accumulate(elapsed);
+            'frames: while let Some(cycles) = self.clock.next_frame() {
+                if rewinding {
+                    self.rewind_frame();
+                    continue;
+                }
+                for _ in 0..cycles {
+                    if let Err(err) = self.emulate_cycle() {
+                        self.report_fault(err);
+                        break 'frames;
+                    }
+                    self.memory_view.note_writes(&self.chip8);
+                    if let Some(reason) = self.debugger.breakpoints.check(&self.chip8) {
+                        self.debugger.last_break = Some(reason);
+                        self.execution_state = ExecutionState::Paused;
+                        break 'frames;
+                    }
+                }
+                self.chip8.tick_timers();
+                if self.profiler_view.recording {
+                    self.profiler.end_frame();
+                }
+                self.memory_view.age_writes();
+                self.rewind.push(&self.chip8);
+            }
         }
+
+        let sample_rate = self.audio_output.sample_rate();
+        let mut samples = vec![0.0; (elapsed.as_secs_f64() * sample_rate as f64) as usize];
+        self.audio_generator.generate(&self.chip8, sample_rate, &mut samples);
+        self.audio_output.write_samples(&samples);
+        if let Some(recording) = self.audio_recording.as_mut() {
+            recording.write_samples(&samples);
         }
-        
-        if self.chip8.delay_timer > 0 { self.chip8.delay_timer -= 1; }
-        if self.chip8.sound_timer > 0 { self.chip8.sound_timer -= 1; }
 
         let key_map = [
             (egui::Key::Num1, 0x1), (egui::Key::Num2, 0x2), (egui::Key::Num3, 0x3), (egui::Key::Num4, 0xC),
@@ -69,12 +568,23 @@ impl eframe::App for MyApp {
             (egui::Key::A, 0x7), (egui::Key::S, 0x8), (egui::Key::D, 0x9), (egui::Key::F, 0xE),
             (egui::Key::Z, 0xA), (egui::Key::X, 0x0), (egui::Key::C, 0xB), (egui::Key::V, 0xF),
         ];
-        ctx.input(|i| {
+        let (save_pressed, load_pressed) = ctx.input(|i| {
             for (key, chip8_key_index) in key_map {
                 self.chip8.keypad[chip8_key_index] = if i.key_down(key) { 1 } else { 0 };
             }
+            (i.key_pressed(egui::Key::F5), i.key_pressed(egui::Key::F9))
         });
+        if save_pressed {
+            self.quick_save();
+        }
+        if load_pressed {
+            self.quick_load();
+        }
         
         ctx.request_repaint_after(Duration::from_millis(2));
     }
+
+    fn on_exit(&mut self, _gl: Option<&eframe::glow::Context>) {
+        self.stop_trace();
+    }
 }
\ No newline at end of file
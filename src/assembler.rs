@@ -0,0 +1,357 @@
+use std::collections::HashMap;
+use std::fmt;
+use std::fs;
+use std::path::{Path, PathBuf};
+
+use crate::chip8::{PROGRAM_START, XO_MEMORY_SIZE};
+
+const MAX_INCLUDE_DEPTH: usize = 16;
+const MAX_CONSTANT_DEPTH: usize = 32;
+
+#[derive(Debug)]
+pub struct AsmError {
+    pub file: String,
+    pub line: usize,
+    pub message: String,
+}
+
+impl fmt::Display for AsmError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}:{}: {}", self.file, self.line, self.message)
+    }
+}
+
+impl std::error::Error for AsmError {}
+
+struct SourceLine {
+    file: String,
+    line: usize,
+    text: String,
+}
+
+impl SourceLine {
+    fn error(&self, message: impl Into<String>) -> AsmError {
+        AsmError { file: self.file.clone(), line: self.line, message: message.into() }
+    }
+}
+
+enum Statement {
+    Instruction { mnemonic: String, operands: Vec<String> },
+    Db(Vec<String>),
+    Dw(Vec<String>),
+}
+
+struct Parsed<'a> {
+    source: &'a SourceLine,
+    statement: Statement,
+}
+
+pub fn assemble(source: &str) -> Result<Vec<u8>, AsmError> {
+    let mut lines = Vec::new();
+    read_lines(source, "<input>", Path::new("."), 0, &mut lines)?;
+    assemble_lines(&lines)
+}
+
+pub fn assemble_file(path: impl AsRef<Path>) -> Result<Vec<u8>, AsmError> {
+    let path = path.as_ref();
+    let name = path.display().to_string();
+    let source = fs::read_to_string(path)
+        .map_err(|err| AsmError { file: name.clone(), line: 0, message: format!("cannot read file: {err}") })?;
+    let mut lines = Vec::new();
+    read_lines(&source, &name, path.parent().unwrap_or(Path::new(".")), 0, &mut lines)?;
+    assemble_lines(&lines)
+}
+
+fn read_lines(source: &str, file: &str, dir: &Path, depth: usize, out: &mut Vec<SourceLine>) -> Result<(), AsmError> {
+    for (index, text) in source.lines().enumerate() {
+        let line = SourceLine { file: file.to_string(), line: index + 1, text: strip_comment(text).trim().to_string() };
+
+        let mut words = line.text.splitn(2, char::is_whitespace);
+        if !words.next().is_some_and(|word| word.eq_ignore_ascii_case("include")) {
+            out.push(line);
+            continue;
+        }
+
+        if depth >= MAX_INCLUDE_DEPTH {
+            return Err(line.error("includes nested too deeply"));
+        }
+        let name = words.next().unwrap_or("").trim().trim_matches('"');
+        if name.is_empty() {
+            return Err(line.error("include needs a file name"));
+        }
+        let path: PathBuf = dir.join(name);
+        let included = fs::read_to_string(&path)
+            .map_err(|err| line.error(format!("cannot include '{}': {err}", path.display())))?;
+        read_lines(&included, &path.display().to_string(), path.parent().unwrap_or(dir), depth + 1, out)?;
+    }
+    Ok(())
+}
+
+fn strip_comment(text: &str) -> &str {
+    text.split(';').next().unwrap_or("")
+}
+
+fn is_identifier(text: &str) -> bool {
+    let mut chars = text.chars();
+    chars.next().is_some_and(|c| c.is_ascii_alphabetic() || c == '_')
+        && chars.all(|c| c.is_ascii_alphanumeric() || c == '_' || c == '.')
+}
+
+fn split_operands(text: &str) -> Vec<String> {
+    if text.trim().is_empty() {
+        return Vec::new();
+    }
+    text.split(',').map(|operand| operand.trim().to_string()).collect()
+}
+
+fn is_long_operand(text: &str) -> bool {
+    text.split_whitespace().next().is_some_and(|word| word.eq_ignore_ascii_case("LONG"))
+}
+
+fn instruction_size(mnemonic: &str, operands: &[String]) -> usize {
+    if mnemonic == "LD" && operands.get(1).is_some_and(|operand| is_long_operand(operand)) { 4 } else { 2 }
+}
+
+fn assemble_lines(lines: &[SourceLine]) -> Result<Vec<u8>, AsmError> {
+    let mut symbols: HashMap<String, Symbol> = HashMap::new();
+    let mut parsed = Vec::new();
+    let mut address = PROGRAM_START;
+
+    for line in lines {
+        let mut text = line.text.as_str();
+
+        while let Some((label, rest)) = text.split_once(':')
+            && is_identifier(label.trim())
+        {
+            define(&mut symbols, line, label.trim(), Symbol::Address(address))?;
+            text = rest.trim();
+        }
+        if text.is_empty() {
+            continue;
+        }
+
+        let (head, rest) = text.split_once(char::is_whitespace).unwrap_or((text, ""));
+        let rest = rest.trim();
+
+        if let Some((keyword, value)) = rest.split_once(char::is_whitespace)
+            && keyword.eq_ignore_ascii_case("equ")
+        {
+            if !is_identifier(head) {
+                return Err(line.error(format!("invalid constant name '{head}'")));
+            }
+            define(&mut symbols, line, head, Symbol::Constant(value.trim().to_string()))?;
+            continue;
+        }
+
+        let mnemonic = head.to_ascii_uppercase();
+        let operands = split_operands(rest);
+        let (size, statement) = match mnemonic.as_str() {
+            "DB" => (operands.len(), Statement::Db(operands)),
+            "DW" => (operands.len() * 2, Statement::Dw(operands)),
+            _ => (instruction_size(&mnemonic, &operands), Statement::Instruction { mnemonic, operands }),
+        };
+        parsed.push(Parsed { source: line, statement });
+        address += size;
+        if address > XO_MEMORY_SIZE {
+            return Err(line.error("program does not fit in memory"));
+        }
+    }
+
+    let mut out = Vec::with_capacity(address - PROGRAM_START);
+    for item in &parsed {
+        let context = Context { symbols: &symbols, line: item.source };
+        match &item.statement {
+            Statement::Db(values) => {
+                for value in values {
+                    out.push(context.value(value, 0xFF)? as u8);
+                }
+            }
+            Statement::Dw(values) => {
+                for value in values {
+                    out.extend_from_slice(&(context.value(value, 0xFFFF)? as u16).to_be_bytes());
+                }
+            }
+            Statement::Instruction { mnemonic, operands } => {
+                let words = context.encode(mnemonic, operands)?;
+                for word in words {
+                    out.extend_from_slice(&word.to_be_bytes());
+                }
+            }
+        }
+    }
+    Ok(out)
+}
+
+enum Symbol {
+    Address(usize),
+    Constant(String),
+}
+
+fn define(symbols: &mut HashMap<String, Symbol>, line: &SourceLine, name: &str, symbol: Symbol) -> Result<(), AsmError> {
+    if symbols.contains_key(name) {
+        return Err(line.error(format!("'{name}' is already defined")));
+    }
+    symbols.insert(name.to_string(), symbol);
+    Ok(())
+}
+
+struct Context<'a> {
+    symbols: &'a HashMap<String, Symbol>,
+    line: &'a SourceLine,
+}
+
+impl Context<'_> {
+    fn error(&self, message: impl Into<String>) -> AsmError {
+        self.line.error(message)
+    }
+
+    fn eval(&self, text: &str, depth: usize) -> Result<i64, AsmError> {
+        let text = text.trim();
+        if text.is_empty() {
+            return Err(self.error("missing value"));
+        }
+
+        let mut total = 0i64;
+        let mut sign = 1;
+        let mut term = String::new();
+        let mut terms = Vec::new();
+        for c in text.chars() {
+            if (c == '+' || c == '-') && !term.trim().is_empty() {
+                terms.push((sign, std::mem::take(&mut term)));
+                sign = if c == '+' { 1 } else { -1 };
+            } else if c == '-' && term.trim().is_empty() {
+                sign = -sign;
+            } else if c != '+' || !term.trim().is_empty() {
+                term.push(c);
+            }
+        }
+        terms.push((sign, term));
+
+        for (sign, term) in terms {
+            total += sign * self.term(term.trim(), depth)?;
+        }
+        Ok(total)
+    }
+
+    fn term(&self, term: &str, depth: usize) -> Result<i64, AsmError> {
+        if term.is_empty() {
+            return Err(self.error("missing value"));
+        }
+        if term.starts_with(|c: char| c.is_ascii_digit()) {
+            let parsed = if let Some(hex) = term.strip_prefix("0x").or_else(|| term.strip_prefix("0X")) {
+                i64::from_str_radix(hex, 16)
+            } else if let Some(binary) = term.strip_prefix("0b").or_else(|| term.strip_prefix("0B")) {
+                i64::from_str_radix(binary, 2)
+            } else {
+                term.parse()
+            };
+            return parsed.map_err(|_| self.error(format!("invalid number '{term}'")));
+        }
+
+        match self.symbols.get(term) {
+            Some(Symbol::Address(address)) => Ok(*address as i64),
+            Some(Symbol::Constant(_)) if depth >= MAX_CONSTANT_DEPTH => {
+                Err(self.error(format!("constant '{term}' is defined in terms of itself")))
+            }
+            Some(Symbol::Constant(expr)) => self.eval(expr, depth + 1),
+            None => Err(self.error(format!("undefined symbol '{term}'"))),
+        }
+    }
+
+    fn value(&self, text: &str, max: i64) -> Result<i64, AsmError> {
+        let value = self.eval(text, 0)?;
+        if !(0..=max).contains(&value) {
+            return Err(self.error(format!("value {value} is out of range (0 to {max:#X})")));
+        }
+        Ok(value)
+    }
+
+    fn register(&self, text: &str) -> Option<u16> {
+        let digit = text.strip_prefix(['V', 'v'])?;
+        if digit.len() != 1 {
+            return None;
+        }
+        u16::from_str_radix(digit, 16).ok()
+    }
+
+    fn expect_register(&self, text: &str) -> Result<u16, AsmError> {
+        self.register(text).ok_or_else(|| self.error(format!("expected a register, found '{text}'")))
+    }
+
+    fn encode(&self, mnemonic: &str, operands: &[String]) -> Result<Vec<u16>, AsmError> {
+        let ops: Vec<&str> = operands.iter().map(String::as_str).collect();
+        let upper: Vec<String> = ops.iter().map(|op| op.to_ascii_uppercase()).collect();
+        let upper: Vec<&str> = upper.iter().map(String::as_str).collect();
+
+        let addr = |text: &str| self.value(text, 0xFFF).map(|value| value as u16);
+        let byte = |text: &str| self.value(text, 0xFF).map(|value| value as u16);
+        let nibble = |text: &str| self.value(text, 0xF).map(|value| value as u16);
+        let vx = |text: &str| self.expect_register(text).map(|x| x << 8);
+        let vy = |text: &str| self.expect_register(text).map(|y| y << 4);
+        let is_reg = |text: &str| self.register(text).is_some();
+
+        let opcode = match (mnemonic, upper.as_slice()) {
+            ("CLS", []) => 0x00E0,
+            ("RET", []) => 0x00EE,
+            ("SCD", [_]) => 0x00C0 | nibble(ops[0])?,
+            ("SCU", [_]) => 0x00D0 | nibble(ops[0])?,
+            ("SCR", []) => 0x00FB,
+            ("SCL", []) => 0x00FC,
+            ("EXIT", []) => 0x00FD,
+            ("LOW", []) => 0x00FE,
+            ("HIGH", []) => 0x00FF,
+            ("SYS", [_]) => addr(ops[0])?,
+            ("JP", ["V0", _]) => 0xB000 | addr(ops[1])?,
+            ("JP", [_]) => 0x1000 | addr(ops[0])?,
+            ("CALL", [_]) => 0x2000 | addr(ops[0])?,
+            ("SE", [_, y]) if is_reg(y) => 0x5000 | vx(ops[0])? | vy(ops[1])?,
+            ("SE", [_, _]) => 0x3000 | vx(ops[0])? | byte(ops[1])?,
+            ("SNE", [_, y]) if is_reg(y) => 0x9000 | vx(ops[0])? | vy(ops[1])?,
+            ("SNE", [_, _]) => 0x4000 | vx(ops[0])? | byte(ops[1])?,
+            ("SAVE", [_, _]) => 0x5002 | vx(ops[0])? | vy(ops[1])?,
+            ("LOAD", [_, _]) => 0x5003 | vx(ops[0])? | vy(ops[1])?,
+            ("LD", ["I", long]) if is_long_operand(long) => {
+                let target = ops[1].split_once(char::is_whitespace).map(|(_, rest)| rest).unwrap_or("");
+                return Ok(vec![0xF000, self.value(target, 0xFFFF)? as u16]);
+            }
+            ("LD", ["I", _]) => 0xA000 | addr(ops[1])?,
+            ("LD", ["DT", _]) => 0xF015 | vx(ops[1])?,
+            ("LD", ["ST", _]) => 0xF018 | vx(ops[1])?,
+            ("LD", ["F", _]) => 0xF029 | vx(ops[1])?,
+            ("LD", ["HF", _]) => 0xF030 | vx(ops[1])?,
+            ("LD", ["B", _]) => 0xF033 | vx(ops[1])?,
+            ("LD", ["[I]", _]) => 0xF055 | vx(ops[1])?,
+            ("LD", ["R", _]) => 0xF075 | vx(ops[1])?,
+            ("LD", [_, "DT"]) => 0xF007 | vx(ops[0])?,
+            ("LD", [_, "K"]) => 0xF00A | vx(ops[0])?,
+            ("LD", [_, "[I]"]) => 0xF065 | vx(ops[0])?,
+            ("LD", [_, "R"]) => 0xF085 | vx(ops[0])?,
+            ("LD", [_, y]) if is_reg(y) => 0x8000 | vx(ops[0])? | vy(ops[1])?,
+            ("LD", [_, _]) => 0x6000 | vx(ops[0])? | byte(ops[1])?,
+            ("ADD", ["I", _]) => 0xF01E | vx(ops[1])?,
+            ("ADD", [_, y]) if is_reg(y) => 0x8004 | vx(ops[0])? | vy(ops[1])?,
+            ("ADD", [_, _]) => 0x7000 | vx(ops[0])? | byte(ops[1])?,
+            ("OR", [_, _]) => 0x8001 | vx(ops[0])? | vy(ops[1])?,
+            ("AND", [_, _]) => 0x8002 | vx(ops[0])? | vy(ops[1])?,
+            ("XOR", [_, _]) => 0x8003 | vx(ops[0])? | vy(ops[1])?,
+            ("SUB", [_, _]) => 0x8005 | vx(ops[0])? | vy(ops[1])?,
+            ("SHR", [_]) => 0x8006 | vx(ops[0])?,
+            ("SHR", [_, _]) => 0x8006 | vx(ops[0])? | vy(ops[1])?,
+            ("SUBN", [_, _]) => 0x8007 | vx(ops[0])? | vy(ops[1])?,
+            ("SHL", [_]) => 0x800E | vx(ops[0])?,
+            ("SHL", [_, _]) => 0x800E | vx(ops[0])? | vy(ops[1])?,
+            ("RND", [_, _]) => 0xC000 | vx(ops[0])? | byte(ops[1])?,
+            ("DRW", [_, _, _]) => 0xD000 | vx(ops[0])? | vy(ops[1])? | nibble(ops[2])?,
+            ("SKP", [_]) => 0xE09E | vx(ops[0])?,
+            ("SKNP", [_]) => 0xE0A1 | vx(ops[0])?,
+            ("PLANE", [_]) => 0xF001 | (nibble(ops[0])? << 8),
+            ("AUDIO", []) => 0xF002,
+            ("PITCH", [_]) => 0xF03A | vx(ops[0])?,
+            _ => {
+                let text = format!("{} {}", mnemonic, ops.join(", "));
+                return Err(self.error(format!("invalid instruction '{}'", text.trim())));
+            }
+        };
+        Ok(vec![opcode])
+    }
+}
@@ -0,0 +1,168 @@
+use std::fs::File;
+use std::io::{self, BufWriter, Seek, SeekFrom, Write};
+use std::path::Path;
+
+use crate::chip8::{AUDIO_PATTERN_SIZE, Chip8};
+
+pub const DEFAULT_SAMPLE_RATE: u32 = 44100;
+
+const PATTERN_BITS: f64 = (AUDIO_PATTERN_SIZE * 8) as f64;
+const WAV_HEADER_SIZE: u32 = 44;
+
+pub trait AudioOutput {
+    fn sample_rate(&self) -> u32;
+    fn write_samples(&mut self, samples: &[f32]);
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct AudioSettings {
+    pub volume: f32,
+    pub frequency: f32,
+    pub muted: bool,
+}
+
+impl Default for AudioSettings {
+    fn default() -> Self {
+        AudioSettings {
+            volume: 0.25,
+            frequency: 440.0,
+            muted: false,
+        }
+    }
+}
+
+pub struct AudioGenerator {
+    pub settings: AudioSettings,
+    position: f64,
+    phase: f64,
+}
+
+impl AudioGenerator {
+    pub fn new() -> Self {
+        AudioGenerator {
+            settings: AudioSettings::default(),
+            position: 0.0,
+            phase: 0.0,
+        }
+    }
+
+    pub fn playback_rate(pitch: u8) -> f64 {
+        4000.0 * 2f64.powf((pitch as f64 - 64.0) / 48.0)
+    }
+
+    pub fn generate(&mut self, chip8: &Chip8, sample_rate: u32, out: &mut [f32]) {
+        if chip8.sound_timer == 0 || self.settings.muted {
+            self.position = 0.0;
+            self.phase = 0.0;
+            out.fill(0.0);
+            return;
+        }
+
+        let volume = self.settings.volume;
+        match chip8.audio_pattern {
+            Some(pattern) => {
+                let step = Self::playback_rate(chip8.pitch) / sample_rate as f64;
+                for sample in out.iter_mut() {
+                    let bit = self.position as usize;
+                    let is_set = pattern[bit / 8] & (0x80 >> (bit % 8)) != 0;
+                    *sample = if is_set { volume } else { -volume };
+                    self.position = (self.position + step) % PATTERN_BITS;
+                }
+            }
+            None => {
+                let step = self.settings.frequency as f64 / sample_rate as f64;
+                for sample in out.iter_mut() {
+                    *sample = if self.phase < 0.5 { volume } else { -volume };
+                    self.phase = (self.phase + step) % 1.0;
+                }
+            }
+        }
+    }
+}
+
+impl Default for AudioGenerator {
+    fn default() -> Self {
+        AudioGenerator::new()
+    }
+}
+
+pub struct NullOutput {
+    sample_rate: u32,
+}
+
+impl NullOutput {
+    pub fn new(sample_rate: u32) -> Self {
+        NullOutput { sample_rate }
+    }
+}
+
+impl AudioOutput for NullOutput {
+    fn sample_rate(&self) -> u32 {
+        self.sample_rate
+    }
+
+    fn write_samples(&mut self, _samples: &[f32]) {}
+}
+
+pub struct WavOutput {
+    writer: BufWriter<File>,
+    sample_rate: u32,
+    data_size: u32,
+}
+
+impl WavOutput {
+    pub fn create(path: impl AsRef<Path>, sample_rate: u32) -> io::Result<Self> {
+        let mut output = WavOutput {
+            writer: BufWriter::new(File::create(path)?),
+            sample_rate,
+            data_size: 0,
+        };
+        output.write_header()?;
+        Ok(output)
+    }
+
+    fn write_header(&mut self) -> io::Result<()> {
+        let w = &mut self.writer;
+        w.write_all(b"RIFF")?;
+        w.write_all(&(WAV_HEADER_SIZE - 8 + self.data_size).to_le_bytes())?;
+        w.write_all(b"WAVEfmt ")?;
+        w.write_all(&16u32.to_le_bytes())?;
+        w.write_all(&1u16.to_le_bytes())?;
+        w.write_all(&1u16.to_le_bytes())?;
+        w.write_all(&self.sample_rate.to_le_bytes())?;
+        w.write_all(&(self.sample_rate * 2).to_le_bytes())?;
+        w.write_all(&2u16.to_le_bytes())?;
+        w.write_all(&16u16.to_le_bytes())?;
+        w.write_all(b"data")?;
+        w.write_all(&self.data_size.to_le_bytes())
+    }
+
+    pub fn finish(&mut self) -> io::Result<()> {
+        self.writer.seek(SeekFrom::Start(0))?;
+        self.write_header()?;
+        self.writer.seek(SeekFrom::End(0))?;
+        self.writer.flush()
+    }
+}
+
+impl AudioOutput for WavOutput {
+    fn sample_rate(&self) -> u32 {
+        self.sample_rate
+    }
+
+    fn write_samples(&mut self, samples: &[f32]) {
+        for &sample in samples {
+            let value = (sample.clamp(-1.0, 1.0) * i16::MAX as f32) as i16;
+            if self.writer.write_all(&value.to_le_bytes()).is_err() {
+                return;
+            }
+            self.data_size += 2;
+        }
+    }
+}
+
+impl Drop for WavOutput {
+    fn drop(&mut self) {
+        let _ = self.finish();
+    }
+}
@@ -0,0 +1,83 @@
+use chip8_emulator::audio::AudioOutput;
+
+#[cfg(feature = "audio")]
+mod cpal_output {
+    use std::collections::VecDeque;
+    use std::sync::{Arc, Mutex};
+
+    use cpal::traits::{DeviceTrait, HostTrait, StreamTrait};
+
+    use chip8_emulator::audio::AudioOutput;
+
+    pub struct CpalOutput {
+        _stream: cpal::Stream,
+        buffer: Arc<Mutex<VecDeque<f32>>>,
+        sample_rate: u32,
+    }
+
+    impl CpalOutput {
+        pub fn open() -> Option<Self> {
+            let device = cpal::default_host().default_output_device()?;
+            let config = device.default_output_config().ok()?;
+            if config.sample_format() != cpal::SampleFormat::F32 {
+                return None;
+            }
+
+            let sample_rate = config.sample_rate().0;
+            let channels = config.channels() as usize;
+            let buffer = Arc::new(Mutex::new(VecDeque::new()));
+            let callback_buffer = Arc::clone(&buffer);
+
+            let stream = device
+                .build_output_stream(
+                    &config.config(),
+                    move |data: &mut [f32], _: &cpal::OutputCallbackInfo| {
+                        let mut buffer = callback_buffer.lock().unwrap();
+                        for frame in data.chunks_mut(channels) {
+                            let sample = buffer.pop_front().unwrap_or(0.0);
+                            frame.fill(sample);
+                        }
+                    },
+                    |err| eprintln!("Audio stream error: {err}"),
+                    None,
+                )
+                .ok()?;
+            stream.play().ok()?;
+
+            Some(CpalOutput { _stream: stream, buffer, sample_rate })
+        }
+    }
+
+    impl AudioOutput for CpalOutput {
+        fn sample_rate(&self) -> u32 {
+            self.sample_rate
+        }
+
+        fn write_samples(&mut self, samples: &[f32]) {
+            let max_latency = self.sample_rate as usize / 10;
+            let mut buffer = self.buffer.lock().unwrap();
+            buffer.extend(samples);
+            if buffer.len() > max_latency {
+                let excess = buffer.len() - max_latency;
+                buffer.drain(..excess);
+            }
+        }
+    }
+}
+
+#[cfg(feature = "audio")]
+pub fn open_default_output() -> Option<Box<dyn AudioOutput>> {
+    let output = cpal_output::CpalOutput::open();
+    if output.is_none() {
+        eprintln!("No usable audio output device, sound is disabled");
+    }
+    output.map(|output| Box::new(output) as Box<dyn AudioOutput>)
+}
+
+// Without cpal the app falls back to `NullOutput`: the beeper still runs and
+// can be recorded to WAV, but nothing reaches the speakers.
+#[cfg(not(feature = "audio"))]
+pub fn open_default_output() -> Option<Box<dyn AudioOutput>> {
+    eprintln!("Built without the `audio` feature, sound is only available through WAV recording");
+    None
+}
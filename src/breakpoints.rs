@@ -0,0 +1,222 @@
+use std::collections::BTreeSet;
+use std::ops::RangeInclusive;
+
+use crate::chip8::{AccessKind, Chip8};
+use crate::expr::{Expr, ExprError};
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum WatchAccess {
+    Read,
+    Write,
+    Any,
+}
+
+impl WatchAccess {
+    pub const ALL: [WatchAccess; 3] = [WatchAccess::Read, WatchAccess::Write, WatchAccess::Any];
+
+    pub fn name(self) -> &'static str {
+        match self {
+            WatchAccess::Read => "read",
+            WatchAccess::Write => "write",
+            WatchAccess::Any => "access",
+        }
+    }
+
+    fn matches(self, kind: AccessKind) -> bool {
+        match self {
+            WatchAccess::Read => kind == AccessKind::Read,
+            WatchAccess::Write => kind == AccessKind::Write,
+            WatchAccess::Any => true,
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct OpcodePattern {
+    mask: u16,
+    value: u16,
+}
+
+impl OpcodePattern {
+    pub fn parse(text: &str) -> Option<Self> {
+        let text = text.trim();
+        if text.len() != 4 {
+            return None;
+        }
+
+        let mut mask = 0;
+        let mut value = 0;
+        for c in text.chars() {
+            mask <<= 4;
+            value <<= 4;
+            match c.to_ascii_uppercase() {
+                'X' | 'Y' | 'N' | 'K' => {}
+                c => {
+                    value |= c.to_digit(16)? as u16;
+                    mask |= 0xF;
+                }
+            }
+        }
+        Some(OpcodePattern { mask, value })
+    }
+
+    pub fn matches(&self, opcode: u16) -> bool {
+        opcode & self.mask == self.value
+    }
+}
+
+#[derive(Debug, Clone)]
+enum WatchKind {
+    Memory { range: RangeInclusive<usize>, access: WatchAccess },
+    Condition { expr: Expr, was_true: bool },
+    Changed { expr: Expr, last: i64 },
+    Opcode(OpcodePattern),
+}
+
+#[derive(Debug, Clone)]
+pub struct Watchpoint {
+    pub label: String,
+    pub enabled: bool,
+    kind: WatchKind,
+}
+
+impl Watchpoint {
+    pub fn memory(range: RangeInclusive<usize>, access: WatchAccess) -> Self {
+        let label = if range.start() == range.end() {
+            format!("{} {:#06X}", access.name(), range.start())
+        } else {
+            format!("{} {:#06X}-{:#06X}", access.name(), range.start(), range.end())
+        };
+        Watchpoint { label, enabled: true, kind: WatchKind::Memory { range, access } }
+    }
+
+    pub fn condition(text: &str, chip8: &Chip8) -> Result<Self, ExprError> {
+        let expr = Expr::parse(text)?;
+        let was_true = expr.is_true(chip8);
+        Ok(Watchpoint {
+            label: format!("if {}", text.trim()),
+            enabled: true,
+            kind: WatchKind::Condition { expr, was_true },
+        })
+    }
+
+    pub fn changed(text: &str, chip8: &Chip8) -> Result<Self, ExprError> {
+        let expr = Expr::parse(text)?;
+        let last = expr.eval(chip8);
+        Ok(Watchpoint { label: format!("change {}", text.trim()), enabled: true, kind: WatchKind::Changed { expr, last } })
+    }
+
+    pub fn opcode(text: &str) -> Option<Self> {
+        let pattern = OpcodePattern::parse(text)?;
+        Some(Watchpoint {
+            label: format!("opcode {}", text.trim().to_ascii_uppercase()),
+            enabled: true,
+            kind: WatchKind::Opcode(pattern),
+        })
+    }
+
+    fn check(&mut self, chip8: &Chip8) -> Option<String> {
+        let cycle = chip8.last_cycle.as_ref();
+        let hit = match &mut self.kind {
+            WatchKind::Memory { range, access } => cycle.and_then(|cycle| {
+                cycle
+                    .accesses
+                    .iter()
+                    .find(|memory| range.contains(&memory.address) && access.matches(memory.kind))
+                    .map(|memory| {
+                        let verb = if memory.kind == AccessKind::Read { "read of" } else { "write to" };
+                        format!("{verb} {:#06X} at {:#06X}", memory.address, cycle.pc)
+                    })
+            }),
+            WatchKind::Condition { expr, was_true } => {
+                let is_true = expr.is_true(chip8);
+                let previous = std::mem::replace(was_true, is_true);
+                (is_true && !previous).then(|| self.label.clone())
+            }
+            WatchKind::Changed { expr, last } => {
+                let value = expr.eval(chip8);
+                let previous = std::mem::replace(last, value);
+                (value != previous).then(|| format!("{}: {:#X} -> {:#X}", self.label, previous, value))
+            }
+            WatchKind::Opcode(pattern) => cycle
+                .filter(|cycle| pattern.matches(cycle.opcode))
+                .map(|cycle| format!("{} ({:04X}) at {:#06X}", self.label, cycle.opcode, cycle.pc)),
+        };
+        hit.filter(|_| self.enabled)
+    }
+}
+
+#[derive(Debug, Clone, Default)]
+pub struct Breakpoints {
+    addresses: BTreeSet<u16>,
+    watchpoints: Vec<Watchpoint>,
+}
+
+impl Breakpoints {
+    pub fn toggle(&mut self, address: u16) {
+        if !self.addresses.remove(&address) {
+            self.addresses.insert(address);
+        }
+    }
+
+    pub fn insert(&mut self, address: u16) {
+        self.addresses.insert(address);
+    }
+
+    pub fn remove(&mut self, address: u16) {
+        self.addresses.remove(&address);
+    }
+
+    pub fn contains(&self, address: u16) -> bool {
+        self.addresses.contains(&address)
+    }
+
+    pub fn clear(&mut self) {
+        self.addresses.clear();
+        self.watchpoints.clear();
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.addresses.is_empty() && self.watchpoints.is_empty()
+    }
+
+    pub fn iter(&self) -> impl Iterator<Item = u16> + '_ {
+        self.addresses.iter().copied()
+    }
+
+    pub fn add_watchpoint(&mut self, watchpoint: Watchpoint) {
+        self.watchpoints.push(watchpoint);
+    }
+
+    pub fn remove_watchpoint(&mut self, index: usize) {
+        if index < self.watchpoints.len() {
+            self.watchpoints.remove(index);
+        }
+    }
+
+    pub fn watchpoints(&self) -> &[Watchpoint] {
+        &self.watchpoints
+    }
+
+    pub fn watchpoints_mut(&mut self) -> &mut [Watchpoint] {
+        &mut self.watchpoints
+    }
+
+    pub fn hit(&self, chip8: &Chip8) -> bool {
+        self.contains(chip8.pc)
+    }
+
+    pub fn check(&mut self, chip8: &Chip8) -> Option<String> {
+        // Only arriving at a breakpoint counts: an instruction that stalls in
+        // place (FX0A without a key, DXYN waiting for vblank) would otherwise
+        // break again on every cycle after resuming.
+        let arrived = chip8.last_cycle.as_ref().is_some_and(|cycle| cycle.pc != chip8.pc);
+        let mut reason = (arrived && self.hit(chip8)).then(|| format!("breakpoint at {:#06X}", chip8.pc));
+        for watchpoint in &mut self.watchpoints {
+            if let Some(hit) = watchpoint.check(chip8) {
+                reason.get_or_insert(hit);
+            }
+        }
+        reason
+    }
+}
@@ -1,13 +1,37 @@
 use std::fs;
+use std::ops::Range;
 
-use rand::Rng;
+use rand::rngs::StdRng;
+use rand::{Rng, SeedableRng};
+
+use crate::coverage::Coverage;
+use crate::error::Chip8Error;
+use crate::journal::UndoJournal;
+use crate::quirks::Quirks;
 
 pub const MEMORY_SIZE: usize  =  4096;
+pub const PROGRAM_START: usize  =  0x200;
+pub const XO_MEMORY_SIZE: usize  =  65536;
 pub const NUM_REGISTERS: usize  =  16;
 pub const STACK_SIZE: usize  =  16;
 pub const KEYPAD_SIZE: usize  =  16;
 pub const DISPLAY_WIDTH: usize  =  64;
 pub const DISPLAY_HEIGHT: usize  =  32;
+pub const HIRES_DISPLAY_WIDTH: usize  =  128;
+pub const HIRES_DISPLAY_HEIGHT: usize  =  64;
+pub const RPL_FLAGS_SIZE: usize  =  16;
+pub const FONTSET_START: usize  =  0x00;
+pub const BIG_FONTSET_START: usize  =  0x50;
+pub const NUM_PLANES: usize  =  2;
+pub const AUDIO_PATTERN_SIZE: usize  =  16;
+pub const DEFAULT_PITCH: u8  =  64;
+
+pub const DISPLAY_PALETTE: [[u8; 3]; 4] = [
+    [0, 0, 0],
+    [100, 255, 100],
+    [255, 140, 60],
+    [255, 255, 200],
+];
 
 pub const CHIP8_FONTSET: [u8; 80] = [
     0xF0, 0x90, 0x90, 0x90, 0xF0,		
@@ -28,8 +52,46 @@ pub const CHIP8_FONTSET: [u8; 80] = [
 	0xF0, 0x80, 0xF0, 0x80, 0x80
 ];
 
+pub const SCHIP_BIG_FONTSET: [u8; 160] = [
+    0x3C, 0x7E, 0xE7, 0xC3, 0xC3, 0xC3, 0xC3, 0xE7, 0x7E, 0x3C,
+    0x18, 0x38, 0x58, 0x18, 0x18, 0x18, 0x18, 0x18, 0x18, 0x3C,
+    0x3E, 0x7F, 0xC3, 0x06, 0x0C, 0x18, 0x30, 0x60, 0xFF, 0xFF,
+    0x3C, 0x7E, 0xC3, 0x03, 0x0E, 0x0E, 0x03, 0xC3, 0x7E, 0x3C,
+    0x06, 0x0E, 0x1E, 0x36, 0x66, 0xC6, 0xFF, 0xFF, 0x06, 0x06,
+    0xFF, 0xFF, 0xC0, 0xC0, 0xFC, 0xFE, 0x03, 0xC3, 0x7E, 0x3C,
+    0x3E, 0x7C, 0xC0, 0xC0, 0xFC, 0xFE, 0xC3, 0xC3, 0x7E, 0x3C,
+    0xFF, 0xFF, 0x03, 0x06, 0x0C, 0x18, 0x30, 0x60, 0x60, 0x60,
+    0x3C, 0x7E, 0xC3, 0xC3, 0x7E, 0x7E, 0xC3, 0xC3, 0x7E, 0x3C,
+    0x3C, 0x7E, 0xC3, 0xC3, 0x7F, 0x3F, 0x03, 0x03, 0x3E, 0x7C,
+    0x7E, 0xFF, 0xC3, 0xC3, 0xC3, 0xFF, 0xFF, 0xC3, 0xC3, 0xC3,
+    0xFC, 0xFC, 0xC3, 0xC3, 0xFC, 0xFC, 0xC3, 0xC3, 0xFC, 0xFC,
+    0x3C, 0xFF, 0xC3, 0xC0, 0xC0, 0xC0, 0xC0, 0xC3, 0xFF, 0x3C,
+    0xFC, 0xFE, 0xC3, 0xC3, 0xC3, 0xC3, 0xC3, 0xC3, 0xFE, 0xFC,
+    0xFF, 0xFF, 0xC0, 0xC0, 0xFF, 0xFF, 0xC0, 0xC0, 0xFF, 0xFF,
+    0xFF, 0xFF, 0xC0, 0xC0, 0xFF, 0xFF, 0xC0, 0xC0, 0xC0, 0xC0,
+];
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum AccessKind {
+    Read,
+    Write,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct MemoryAccess {
+    pub address: usize,
+    pub kind: AccessKind,
+}
+
+#[derive(Debug, Clone, Default)]
+pub struct CycleInfo {
+    pub pc: u16,
+    pub opcode: u16,
+    pub accesses: Vec<MemoryAccess>,
+}
+
 pub struct Chip8 {
-    pub memory: [u8; MEMORY_SIZE],
+    pub memory: Vec<u8>,
     pub v: [u8; NUM_REGISTERS],
     pub i: u16,
     pub pc: u16,
@@ -37,61 +99,264 @@ pub struct Chip8 {
     pub sp: u8,
     pub delay_timer: u8,
     pub sound_timer: u8,
-    pub display: [u8; DISPLAY_WIDTH * DISPLAY_HEIGHT],
+    pub audio_pattern: Option<[u8; AUDIO_PATTERN_SIZE]>,
+    pub pitch: u8,
+    pub display: Vec<u8>,
+    pub planes: u8,
+    pub hires: bool,
+    pub halted: bool,
+    pub rpl_flags: [u8; RPL_FLAGS_SIZE],
     pub keypad: [u8; KEYPAD_SIZE],
+    pub quirks: Quirks,
+    pub vblank: bool,
+    pub journal: UndoJournal,
+    pub last_cycle: Option<CycleInfo>,
+    pub coverage: Option<Coverage>,
+    rng: StdRng,
 }
 
 impl Chip8 {
-    fn handle_unknown_opcode(&mut self, opcode: u16) {
-        println!("Ошибка: неизвестный или нереализованный опкод: {:#04X}", opcode);
-        self.pc += 2;
+    pub fn new() -> Self {
+        Self::with_memory_size(MEMORY_SIZE)
     }
 
-    pub fn new() -> Self {
-        let mut memory = [0u8; MEMORY_SIZE];
+    pub fn with_memory_size(memory_size: usize) -> Self {
+        let mut memory = vec![0u8; memory_size];
 
-        memory[0..80].copy_from_slice(&CHIP8_FONTSET);
+        memory[FONTSET_START..FONTSET_START + CHIP8_FONTSET.len()].copy_from_slice(&CHIP8_FONTSET);
+        memory[BIG_FONTSET_START..BIG_FONTSET_START + SCHIP_BIG_FONTSET.len()]
+            .copy_from_slice(&SCHIP_BIG_FONTSET);
 
         Chip8 {
-            pc: 0x200,
+            pc: PROGRAM_START as u16,
             i: 0,
             sp: 0,
             delay_timer: 0,
             sound_timer: 0,
-            memory: memory,
+            audio_pattern: None,
+            pitch: DEFAULT_PITCH,
+            memory,
             v: [0; NUM_REGISTERS],
             stack: [0; STACK_SIZE],
-            display: [0; DISPLAY_WIDTH * DISPLAY_HEIGHT],
+            display: vec![0; DISPLAY_WIDTH * DISPLAY_HEIGHT],
+            planes: 0x1,
+            hires: false,
+            halted: false,
+            rpl_flags: [0; RPL_FLAGS_SIZE],
             keypad: [0; KEYPAD_SIZE],
+            quirks: Quirks::default(),
+            vblank: false,
+            journal: UndoJournal::default(),
+            last_cycle: None,
+            coverage: None,
+            rng: StdRng::from_os_rng(),
         }
     }
 
-    pub fn chip8_load_rom(&mut self, path: &str) {
-        const PROGRAM_START: usize = 0x200;
+    pub fn seed_rng(&mut self, seed: u64) {
+        self.rng = StdRng::seed_from_u64(seed);
+    }
 
-        let Ok(data) = fs::read(path) else {
-            println!("Ошибка: невозможно прочитать файл {path}");
-            return;
-        };
+    pub fn waiting_for_vblank(&self) -> bool {
+        let pc = self.pc as usize;
+        self.quirks.display_wait && !self.vblank && self.memory.get(pc).is_some_and(|&hi| hi >> 4 == 0xD)
+    }
+
+    pub fn display_width(&self) -> usize {
+        if self.hires { HIRES_DISPLAY_WIDTH } else { DISPLAY_WIDTH }
+    }
+
+    pub fn display_height(&self) -> usize {
+        if self.hires { HIRES_DISPLAY_HEIGHT } else { DISPLAY_HEIGHT }
+    }
+
+    fn set_resolution(&mut self, hires: bool) {
+        self.journal.record_display(&self.display);
+        self.hires = hires;
+        self.display = vec![0; self.display_width() * self.display_height()];
+    }
+
+    fn shift_display(&mut self, dx: isize, dy: isize) {
+        let width = self.display_width() as isize;
+        let height = self.display_height() as isize;
+        let source = self.display.clone();
+        let mask = self.planes;
+        self.journal.record_display(&source);
+
+        for y in 0..height {
+            for x in 0..width {
+                let (src_x, src_y) = (x - dx, y - dy);
+                let shifted = if (0..width).contains(&src_x) && (0..height).contains(&src_y) {
+                    source[(src_y * width + src_x) as usize]
+                } else {
+                    0
+                };
+                let pixel = &mut self.display[(y * width + x) as usize];
+                *pixel = (*pixel & !mask) | (shifted & mask);
+            }
+        }
+    }
+
+    fn memory_range(&self, start: usize, len: usize) -> Result<Range<usize>, Chip8Error> {
+        let end = start + len;
+        if end > self.memory.len() {
+            return Err(Chip8Error::MemoryOutOfBounds { address: end - 1, pc: self.pc });
+        }
+        Ok(start..end)
+    }
+
+    fn read_range(&mut self, start: usize, len: usize) -> Result<Range<usize>, Chip8Error> {
+        let range = self.memory_range(start, len)?;
+        self.record_access(range.clone(), AccessKind::Read);
+        Ok(range)
+    }
 
-        if data.len() > (MEMORY_SIZE - 0x200) {
-            println!("Ошибка: ROM файл превышает допустимый размер");
+    fn write_memory(&mut self, address: usize, value: u8) {
+        self.journal.record_memory(address, self.memory[address]);
+        self.record_access(address..address + 1, AccessKind::Write);
+        self.memory[address] = value;
+    }
+
+    fn record_access(&mut self, range: Range<usize>, kind: AccessKind) {
+        if let Some(cycle) = self.last_cycle.as_mut() {
+            cycle.accesses.extend(range.map(|address| MemoryAccess { address, kind }));
+        }
+    }
+
+    fn key_index(&self, register: usize) -> Result<usize, Chip8Error> {
+        let key = self.v[register];
+        if key as usize >= KEYPAD_SIZE {
+            return Err(Chip8Error::InvalidKey { key, address: self.pc });
+        }
+        Ok(key as usize)
+    }
+
+    fn unknown_opcode(&self, opcode: u16) -> Chip8Error {
+        Chip8Error::UnknownOpcode { opcode, address: self.pc }
+    }
+
+    fn advance_pc(&mut self, bytes: u16) {
+        self.pc = ((self.pc as usize + bytes as usize) % self.memory.len()) as u16;
+    }
+
+    fn skip_if(&mut self, condition: bool) {
+        if !condition {
+            self.advance_pc(2);
             return;
         }
 
+        let next = self.pc as usize + 2;
+        let is_long_load = self.memory.get(next) == Some(&0xF0) && self.memory.get(next + 1) == Some(&0x00);
+        self.advance_pc(if is_long_load { 6 } else { 4 });
+    }
+
+    fn draw_sprite(&mut self, x_coord: usize, y_coord: usize, height: usize, wide: bool) -> Result<(), Chip8Error> {
+        let width = self.display_width();
+        let display_height = self.display_height();
+        let sprite_width = if wide { 16 } else { 8 };
+        let bytes_per_row = sprite_width / 8;
+        let plane_count = self.planes.count_ones() as usize;
+        self.read_range(self.i as usize, plane_count * height * bytes_per_row)?;
+
+        let x_coord = x_coord % width;
+        let y_coord = y_coord % display_height;
+
+        self.v[0xF] = 0;
+
+        let mut sprite_address = self.i as usize;
+        for plane_index in 0..NUM_PLANES {
+            let plane = 1u8 << plane_index;
+            if self.planes & plane == 0 {
+                continue;
+            }
+
+            for row in 0..height {
+                let row_address = sprite_address + row * bytes_per_row;
+                if self.quirks.clip_sprites && y_coord + row >= display_height {
+                    break;
+                }
+                let screen_y = (y_coord + row) % display_height;
+
+                let sprite_row = if wide {
+                    ((self.memory[row_address] as u16) << 8) | self.memory[row_address + 1] as u16
+                } else {
+                    (self.memory[row_address] as u16) << 8
+                };
+
+                for bit in 0..sprite_width {
+                    if self.quirks.clip_sprites && x_coord + bit >= width {
+                        break;
+                    }
+                    let screen_x = (x_coord + bit) % width;
+
+                    if (sprite_row & (0x8000 >> bit)) != 0 {
+                        let pixel_index = screen_y * width + screen_x;
+
+                        if self.display[pixel_index] & plane != 0 {
+                            self.v[0xF] = 1;
+                        }
+
+                        self.journal.record_pixel(pixel_index, self.display[pixel_index]);
+
+                        self.display[pixel_index] ^= plane;
+                    }
+                }
+            }
+            sprite_address += height * bytes_per_row;
+        }
+        Ok(())
+    }
+
+    pub fn load_rom(&mut self, path: &str) -> Result<(), Chip8Error> {
+        let data = fs::read(path).map_err(|source| Chip8Error::RomRead { path: path.to_string(), source })?;
+        self.load_rom_bytes(&data)
+    }
+
+    pub fn load_rom_bytes(&mut self, data: &[u8]) -> Result<(), Chip8Error> {
+        let capacity = self.memory.len() - PROGRAM_START;
+        if data.len() > capacity {
+            return Err(Chip8Error::RomTooLarge { size: data.len(), capacity });
+        }
+
         let end_address = PROGRAM_START + data.len();
 
-        self.memory[PROGRAM_START .. end_address].copy_from_slice(&data); 
+        self.memory[PROGRAM_START .. end_address].copy_from_slice(data); 
+        Ok(())
+    }
 
-        println!("ROM '{}' ({} bytes) успешно загружен в память", path, data.len());
+    pub fn tick_timers(&mut self) {
+        self.delay_timer = self.delay_timer.saturating_sub(1);
+        self.sound_timer = self.sound_timer.saturating_sub(1);
+        self.vblank = true;
     }
 
-    pub fn chip8_emulate_cycle(&mut self) {
-        let pc = self.pc as usize;
+    pub fn emulate_cycle(&mut self) -> Result<(), Chip8Error> {
+        self.last_cycle = None;
+        if self.halted {
+            return Ok(());
+        }
+
+        self.journal_begin();
+        let result = self.execute_instruction();
+        match result {
+            Ok(()) => self.journal_commit(),
+            Err(_) => self.journal.discard(),
+        }
+        if result.is_ok()
+            && let (Some(coverage), Some(cycle)) = (self.coverage.as_mut(), self.last_cycle.as_ref())
+        {
+            coverage.record(cycle, self.pc);
+        }
+        result
+    }
+
+    fn execute_instruction(&mut self) -> Result<(), Chip8Error> {
+        let pc = self.memory_range(self.pc as usize, 2)?.start;
         let hi = self.memory[pc] as u16;  
         let lo = self.memory[pc + 1] as u16;
 
         let opcode = (hi << 8) | lo;
+        self.last_cycle = Some(CycleInfo { pc: self.pc, opcode, accesses: Vec::new() });
 
         let x   = ((opcode & 0x0F00) >> 8) as usize; 
         let y   = ((opcode & 0x00F0) >> 4) as usize; 
@@ -102,177 +367,229 @@ impl Chip8 {
         match (opcode & 0xF000) >> 12 {
             0x0 => match nn {
                 0xE0 => { 
-                    self.display.fill(0);
-                    self.pc += 2;
+                    self.journal.record_display(&self.display);
+                    let mask = self.planes;
+                    self.display.iter_mut().for_each(|pixel| *pixel &= !mask);
+                    self.advance_pc(2);
                 },
                 0xEE => { 
+                    if self.sp == 0 {
+                        return Err(Chip8Error::StackUnderflow { address: self.pc });
+                    }
                     self.sp -= 1;
                     self.pc = self.stack[self.sp as usize];
-                    self.pc += 2;
+                    self.advance_pc(2);
+                },
+                0xC0..=0xCF => {
+                    self.shift_display(0, n as isize);
+                    self.advance_pc(2);
+                },
+                0xD0..=0xDF => {
+                    self.shift_display(0, -(n as isize));
+                    self.advance_pc(2);
+                },
+                0xFB => {
+                    self.shift_display(4, 0);
+                    self.advance_pc(2);
+                },
+                0xFC => {
+                    self.shift_display(-4, 0);
+                    self.advance_pc(2);
+                },
+                0xFD => {
+                    self.halted = true;
+                },
+                0xFE => {
+                    self.set_resolution(false);
+                    self.advance_pc(2);
+                },
+                0xFF => {
+                    self.set_resolution(true);
+                    self.advance_pc(2);
                 },
                 _ => {
-                    
+                    return Err(self.unknown_opcode(opcode));
                 },
             },
             0x1 => self.pc = nnn, 
             0x2 => { 
+                if self.sp as usize >= STACK_SIZE {
+                    return Err(Chip8Error::StackOverflow { address: self.pc });
+                }
                 self.stack[self.sp as usize] = self.pc;
                 self.sp += 1;
                 self.pc = nnn;
             },
             0x3 => { 
-                if self.v[x] == nn {
-                    self.pc += 4;
-                } else {
-                    self.pc += 2;
-                }
+                self.skip_if(self.v[x] == nn);
             },
             0x4 => {
-                if self.v[x] != nn {
-                    self.pc += 4;
-                } else {
-                    self.pc += 2;
-                }
+                self.skip_if(self.v[x] != nn);
             },
             0x5 => {
-                if self.v[x] == self.v[y] {
-                    self.pc += 4;
-                } else {
-                    self.pc += 2;
+                match n {
+                    0x0 => {
+                        self.skip_if(self.v[x] == self.v[y]);
+                    },
+                    0x2 => {
+                        let registers = register_range(x, y);
+                        let range = self.memory_range(self.i as usize, registers.len())?;
+                        for (address, reg) in range.zip(registers) {
+                            self.write_memory(address, self.v[reg]);
+                        }
+                        self.advance_pc(2);
+                    },
+                    0x3 => {
+                        let registers = register_range(x, y);
+                        let range = self.read_range(self.i as usize, registers.len())?;
+                        for (address, reg) in range.zip(registers) {
+                            self.v[reg] = self.memory[address];
+                        }
+                        self.advance_pc(2);
+                    },
+                    _ => {
+                        return Err(self.unknown_opcode(opcode));
+                    },
                 }
             },
             0x6 => { 
                 self.v[x] = nn;
-                self.pc += 2;
+                self.advance_pc(2);
             },
             0x7 => { 
                 self.v[x] = self.v[x].wrapping_add(nn);
-                self.pc += 2;
+                self.advance_pc(2);
             },
             0x8 => { 
                 match n {
                     0x0 => { 
                         self.v[x] = self.v[y];
-                        self.pc += 2;
+                        self.advance_pc(2);
                     },
                     0x1 => { 
                         self.v[x] |= self.v[y];
-                        self.pc += 2;
+                        if self.quirks.vf_reset {
+                            self.v[0xF] = 0;
+                        }
+                        self.advance_pc(2);
                     },
                     0x2 => {
                         self.v[x] &= self.v[y];
-                        self.pc += 2;
+                        if self.quirks.vf_reset {
+                            self.v[0xF] = 0;
+                        }
+                        self.advance_pc(2);
                     },
                     0x3 => {
                         self.v[x] ^= self.v[y];
-                        self.pc += 2;
+                        if self.quirks.vf_reset {
+                            self.v[0xF] = 0;
+                        }
+                        self.advance_pc(2);
                     },
                     0x4 => {
                         let (sum, carry) = self.v[x].overflowing_add(self.v[y]);
                         self.v[x] = sum;
                         self.v[0xF] = carry as u8;
-                        self.pc += 2;
+                        self.advance_pc(2);
                     },
                     0x5 => { 
                         let (result, borrow) = self.v[x].overflowing_sub(self.v[y]);
                         self.v[x] = result;
                         self.v[0xF] = if borrow { 0 } else { 1 }; 
-                        self.pc += 2;
+                        self.advance_pc(2);
                     },
                     0x6 => {
-                        self.v[0xF] = self.v[x] & 0x1;
-                        self.v[x] >>= 1;
-                        self.pc += 2;
+                        let value = if self.quirks.shift_uses_vy { self.v[y] } else { self.v[x] };
+                        self.v[x] = value >> 1;
+                        self.v[0xF] = value & 0x1;
+                        self.advance_pc(2);
                     },
                     0x7 => {
                         let (result, borrow) = self.v[y].overflowing_sub(self.v[x]);
                         self.v[x] = result;
                         self.v[0xF] = if borrow { 0 } else { 1 }; 
-                        self.pc += 2;
+                        self.advance_pc(2);
                     },
                     0xE => {
-                        self.v[0xF] = (self.v[x] >> 7) & 0x1;
-                        self.v[x] <<= 1;
-                        self.pc += 2;
+                        let value = if self.quirks.shift_uses_vy { self.v[y] } else { self.v[x] };
+                        self.v[x] = value << 1;
+                        self.v[0xF] = (value >> 7) & 0x1;
+                        self.advance_pc(2);
                     }
                     _ => {
-                        self.handle_unknown_opcode(opcode);
+                        return Err(self.unknown_opcode(opcode));
                     },
                 }
             },
             0x9 => {
-                if self.v[x] != self.v[y] {
-                    self.pc += 4;
-                } else {
-                    self.pc += 2;
-                }
+                self.skip_if(self.v[x] != self.v[y]);
             },
             0xA => { 
                 self.i = nnn;
-                self.pc += 2;
+                self.advance_pc(2);
+            },
+            0xB => {
+                let offset = if self.quirks.jump_uses_vx { self.v[x] } else { self.v[0] };
+                self.pc = nnn + (offset as u16);
             },
-            0xB => self.pc = nnn + (self.v[0] as u16),
             0xC => {
-                let mut rng = rand::rng();
-                let random_byte: u8 = rng.random();
+                let random_byte: u8 = self.rng.random();
                 self.v[x] = random_byte & nn;
-                self.pc += 2;
+                self.advance_pc(2);
             },
             0xD => {
-                self.v[0xF] = 0;
-
-                let x_coord = self.v[x] as usize;
-                let y_coord = self.v[y] as usize;
-                let height = n as usize;
-
-                for row in 0..height {
-                    let sprite_byte = self.memory[self.i as usize + row];
-                    let screen_y = (y_coord + row) % DISPLAY_HEIGHT;
-
-                    for bit in 0..8 {
-                        let screen_x = (x_coord + bit) % DISPLAY_WIDTH;
-
-                        if (sprite_byte & (0x80 >> bit)) != 0 {
-                            let pixel_index = screen_y * DISPLAY_WIDTH +screen_x;
-
-                            if self.display[pixel_index] == 1 {
-                                self.v[0xF] = 1;
-                            }
-
-                            self.display[pixel_index] ^= 1;
-                        }
+                if self.quirks.display_wait {
+                    if !self.vblank {
+                        return Ok(());
                     }
+                    self.vblank = false;
                 }
-                self.pc += 2;
+
+                // Interpreters without SUPER-CHIP sprites draw nothing for DXY0 in lores.
+                let (height, wide) = match n {
+                    0 if self.hires || self.quirks.lores_big_sprites => (16, true),
+                    _ => (n as usize, false),
+                };
+                self.draw_sprite(self.v[x] as usize, self.v[y] as usize, height, wide)?;
+                self.advance_pc(2);
             },
             0xE => {
                 match nn {
                     0x9E => {
-                        let key_index = self.v[x] as usize;
-                        if self.keypad[key_index] == 1 {
-                            self.pc += 4;
-                        } else {
-                            self.pc += 2;
-                        }
+                        let key_index = self.key_index(x)?;
+                        self.skip_if(self.keypad[key_index] == 1);
                     },
                     0xA1 => {
-                        let key_index = self.v[x] as usize;
-                        if self.keypad[key_index] == 0 {
-                            self.pc += 4;
-                        } else {
-                            self.pc += 2;
-                        }
+                        let key_index = self.key_index(x)?;
+                        self.skip_if(self.keypad[key_index] == 0);
                     },
                     _ => {
-                        self.handle_unknown_opcode(opcode);
+                        return Err(self.unknown_opcode(opcode));
                     },
                 }
             },
             0xF => {
                 match nn {
+                    0x00 if x == 0 => {
+                        let next = self.memory_range(pc + 2, 2)?.start;
+                        self.i = ((self.memory[next] as u16) << 8) | self.memory[next + 1] as u16;
+                        self.advance_pc(4);
+                    },
+                    0x01 => {
+                        self.planes = x as u8 & 0x3;
+                        self.advance_pc(2);
+                    },
+                    0x02 if x == 0 => {
+                        let range = self.read_range(self.i as usize, AUDIO_PATTERN_SIZE)?;
+                        let mut pattern = [0u8; AUDIO_PATTERN_SIZE];
+                        pattern.copy_from_slice(&self.memory[range]);
+                        self.audio_pattern = Some(pattern);
+                        self.advance_pc(2);
+                    },
                     0x07 => {
                         self.v[x] = self.delay_timer;
-                        self.pc += 2;
+                        self.advance_pc(2);
                     },
                     0x0A => {
                         let mut key_pressed = false;
@@ -285,55 +602,90 @@ impl Chip8 {
                         }
 
                         if key_pressed {
-                            self.pc += 2; 
+                            self.advance_pc(2); 
                         }
                     },
                     0x15 => {
                         self.delay_timer = self.v[x];
-                        self.pc += 2;
+                        self.advance_pc(2);
                     },
                     0x18 => {
                         self.sound_timer = self.v[x];
-                        self.pc += 2;
+                        self.advance_pc(2);
                     },
                     0x1E => {
-                        self.i += self.v[x] as u16;
-                        self.pc += 2;
+                        self.i = self.i.wrapping_add(self.v[x] as u16);
+                        self.advance_pc(2);
                     },
                     0x29 => {
                         self.i = (self.v[x] as u16) * 5;
-                        self.pc += 2;
+                        self.advance_pc(2);
+                    },
+                    0x30 => {
+                        self.i = (BIG_FONTSET_START + (self.v[x] as usize & 0xF) * 10) as u16;
+                        self.advance_pc(2);
                     },
                     0x33 => {
                         let value = self.v[x];
-                        let i = self.i as usize;
-                        self.memory[i]     = value / 100;        
-                        self.memory[i + 1] = (value / 10) % 10;  
-                        self.memory[i + 2] = value % 10;         
-                        self.pc += 2;
+                        let i = self.memory_range(self.i as usize, 3)?.start;
+                        self.write_memory(i, value / 100);
+                        self.write_memory(i + 1, (value / 10) % 10);
+                        self.write_memory(i + 2, value % 10);
+                        self.advance_pc(2);
+                    },
+                    0x3A => {
+                        self.pitch = self.v[x];
+                        self.advance_pc(2);
                     },
                     0x55 => {
-                        let i_addr = self.i as usize;
-                        for i in 0..=x { 
-                            self.memory[i_addr + i] = self.v[i];
+                        let range = self.memory_range(self.i as usize, x + 1)?;
+                        for (address, reg) in range.zip(0..=x) {
+                            self.write_memory(address, self.v[reg]);
                         }
-                        self.pc += 2;
+                        if self.quirks.load_store_increments_i {
+                            self.i = self.i.wrapping_add(x as u16 + 1);
+                        }
+                        self.advance_pc(2);
                     },
                     0x65 => {
-                        let i_addr = self.i as usize;
-                        for i in 0..=x { 
-                            self.v[i] = self.memory[i_addr + i];
+                        let range = self.read_range(self.i as usize, x + 1)?;
+                        self.v[..=x].copy_from_slice(&self.memory[range]);
+                        if self.quirks.load_store_increments_i {
+                            self.i = self.i.wrapping_add(x as u16 + 1);
                         }
-                        self.pc += 2;
+                        self.advance_pc(2);
+                    },
+                    0x75 => {
+                        self.rpl_flags[..=x].copy_from_slice(&self.v[..=x]);
+                        self.advance_pc(2);
+                    },
+                    0x85 => {
+                        self.v[..=x].copy_from_slice(&self.rpl_flags[..=x]);
+                        self.advance_pc(2);
                     },
                     _ => {
-                        self.handle_unknown_opcode(opcode);
+                        return Err(self.unknown_opcode(opcode));
                     },
                 }
             }
             _ => {
-                self.handle_unknown_opcode(opcode);
+                return Err(self.unknown_opcode(opcode));
             },
         }
+        Ok(())
+    }
+}
+
+impl Default for Chip8 {
+    fn default() -> Self {
+        Chip8::new()
     }
-}
\ No newline at end of file
+}
+
+fn register_range(x: usize, y: usize) -> Vec<usize> {
+    if x <= y {
+        (x..=y).collect()
+    } else {
+        (y..=x).rev().collect()
+    }
+}
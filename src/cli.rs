@@ -0,0 +1,456 @@
+use std::fs::{self, File};
+use std::io::{self, BufWriter, Write};
+use std::ops::RangeInclusive;
+use std::path::Path;
+use std::process::ExitCode;
+
+use chip8_emulator::assembler;
+use chip8_emulator::clock::Clock;
+use chip8_emulator::coverage::Coverage;
+use chip8_emulator::error::Chip8Error;
+use chip8_emulator::chip8::Chip8;
+use chip8_emulator::headless::{self, HeadlessRunner, KeyScript};
+use chip8_emulator::listing::{RomListing, Syntax};
+use chip8_emulator::octo;
+use chip8_emulator::profiler::Profiler;
+use chip8_emulator::quirks::QuirksPreset;
+use chip8_emulator::symbols::SymbolTable;
+use chip8_emulator::trace::{self, TraceMode, Tracer};
+use chip8_emulator::tracediff::{self, DiffOutcome, ReferenceState};
+
+pub const USAGE: &str = "\
+Usage:
+  chip8_emulator [rom.ch8 | source.8o]
+  chip8_emulator run <rom.ch8 | source.8o> [options]
+  chip8_emulator disasm <rom.ch8> [--syntax cowgod|octo] [--output <file>]
+  chip8_emulator asm <source.asm> [--output <rom.ch8>]
+  chip8_emulator diff-trace <rom.ch8> <reference.log> [options]
+
+Options for `run`:
+  --frames <n>          number of 60 Hz frames to emulate (default 600)
+  --ips <n>             instructions per second
+  --quirks <preset>     modern, vip, chip48, schip or xochip
+  --keys <file>         key script with `<frame> <key> <down|up>` lines
+  --seed <n>            seed for the CXNN random number generator (default 0)
+  --screenshot <file>   write the final display as PNG
+  --dump-state <file>   write registers and display as JSON
+  --trace <file>        log every executed instruction with the registers
+  --trace-last <n>      only keep the last n instructions, written on exit or fault
+  --trace-range <list>  only trace addresses in ranges like 0x200-0x2FF,0x340
+  --profile <file>      write per-address execution counts as CSV
+  --profile-folded <file>
+                        write cycles per call stack in flamegraph folded format
+  --coverage <file>     write a disassembly annotated with execution counts
+  --coverage-lcov <file>
+                        write coverage as an lcov tracefile for the --coverage
+                        listing
+
+Options for `disasm`:
+  --syntax <syntax>     cowgod (default) or octo
+  --output <file>       write the listing to a file instead of stdout
+
+Options for `asm`:
+  --output <file>       ROM to write (default: the source path with .ch8)
+
+Options for `diff-trace`:
+  --ips <n>             instructions per second the reference ran at
+  --quirks <preset>     modern, vip, chip48, schip or xochip
+  --context <n>         matching instructions to show before a divergence (default 8)";
+
+struct RunOptions {
+    rom: String,
+    frames: u64,
+    ips: Option<u32>,
+    quirks: QuirksPreset,
+    keys: Option<String>,
+    seed: u64,
+    screenshot: Option<String>,
+    dump_state: Option<String>,
+    trace: Option<String>,
+    trace_last: Option<usize>,
+    trace_ranges: Vec<RangeInclusive<u16>>,
+    profile: Option<String>,
+    profile_folded: Option<String>,
+    coverage: Option<String>,
+    coverage_lcov: Option<String>,
+}
+
+fn parse_run_options(args: &[String]) -> Result<RunOptions, String> {
+    let mut options = RunOptions {
+        rom: String::new(),
+        frames: 600,
+        ips: None,
+        quirks: QuirksPreset::default(),
+        keys: None,
+        seed: 0,
+        screenshot: None,
+        dump_state: None,
+        trace: None,
+        trace_last: None,
+        trace_ranges: Vec::new(),
+        profile: None,
+        profile_folded: None,
+        coverage: None,
+        coverage_lcov: None,
+    };
+    let mut rom = None;
+
+    let mut args = args.iter();
+    while let Some(arg) = args.next() {
+        let mut value = || args.next().cloned().ok_or_else(|| format!("missing value for {arg}"));
+        match arg.as_str() {
+            "--frames" => options.frames = value()?.parse().map_err(|_| "invalid --frames value")?,
+            "--ips" => options.ips = Some(parse_ips(&value()?)?),
+            "--quirks" => {
+                let id = value()?;
+                options.quirks = QuirksPreset::from_id(&id).ok_or_else(|| format!("unknown quirks preset '{id}'"))?;
+            }
+            "--keys" => options.keys = Some(value()?),
+            "--seed" => options.seed = value()?.parse().map_err(|_| "invalid --seed value")?,
+            "--screenshot" => options.screenshot = Some(value()?),
+            "--dump-state" => options.dump_state = Some(value()?),
+            "--trace" => options.trace = Some(value()?),
+            "--trace-last" => options.trace_last = Some(value()?.parse().map_err(|_| "invalid --trace-last value")?),
+            "--profile" => options.profile = Some(value()?),
+            "--profile-folded" => options.profile_folded = Some(value()?),
+            "--coverage" => options.coverage = Some(value()?),
+            "--coverage-lcov" => options.coverage_lcov = Some(value()?),
+            "--trace-range" => {
+                options.trace_ranges = trace::parse_ranges(&value()?).ok_or("invalid --trace-range value")?;
+            }
+            _ if arg.starts_with("--") => return Err(format!("unknown option {arg}")),
+            _ if rom.is_none() => rom = Some(arg.clone()),
+            _ => return Err(format!("unexpected argument {arg}")),
+        }
+    }
+
+    options.rom = rom.ok_or("missing ROM path")?;
+    if options.trace.is_none() && (options.trace_last.is_some() || !options.trace_ranges.is_empty()) {
+        return Err("--trace-last and --trace-range need --trace".to_string());
+    }
+    if options.coverage.is_none() && options.coverage_lcov.is_some() {
+        return Err("--coverage-lcov needs --coverage, the listing its line numbers refer to".to_string());
+    }
+    Ok(options)
+}
+
+fn parse_ips(text: &str) -> Result<u32, String> {
+    text.parse().ok().filter(|&ips| ips > 0).ok_or_else(|| "invalid --ips value, expected a number above 0".to_string())
+}
+
+pub fn run_headless(args: &[String]) -> ExitCode {
+    let options = match parse_run_options(args) {
+        Ok(options) => options,
+        Err(err) => {
+            eprintln!("error: {err}\n\n{USAGE}");
+            return ExitCode::FAILURE;
+        }
+    };
+
+    match execute_run(&options) {
+        Ok(true) => ExitCode::SUCCESS,
+        Ok(false) => ExitCode::FAILURE,
+        Err(err) => {
+            eprintln!("error: {err}");
+            ExitCode::FAILURE
+        }
+    }
+}
+
+struct Program {
+    chip8: Chip8,
+    rom: Vec<u8>,
+    symbols: SymbolTable,
+}
+
+fn load_program(rom: &str, quirks: QuirksPreset) -> Result<Program, String> {
+    let mut chip8 = Chip8::with_memory_size(quirks.memory_size());
+    chip8.quirks = quirks.quirks();
+    let (data, mut symbols) = if octo::is_octo_source(rom) {
+        let program = chip8.load_octo(rom).map_err(|err| err.to_string())?;
+        (program.rom, program.labels.into())
+    } else {
+        let data = fs::read(rom)
+            .map_err(|source| Chip8Error::RomRead { path: rom.to_string(), source }.to_string())?;
+        chip8.load_rom_bytes(&data).map_err(|err| err.to_string())?;
+        (data, SymbolTable::new())
+    };
+
+    let symbol_file = format!("{rom}.sym");
+    if Path::new(&symbol_file).exists() {
+        let text = fs::read_to_string(&symbol_file).map_err(|err| format!("cannot read '{symbol_file}': {err}"))?;
+        symbols.extend(SymbolTable::parse(&text).map_err(|err| format!("{symbol_file}: {err}"))?);
+    }
+    Ok(Program { chip8, rom: data, symbols })
+}
+
+fn write_report(path: &str, write: impl FnOnce(&mut dyn Write) -> io::Result<()>) -> Result<(), String> {
+    let file = File::create(path).map_err(|err| format!("cannot create '{path}': {err}"))?;
+    let mut out = BufWriter::new(file);
+    write(&mut out).and_then(|()| out.flush()).map_err(|err| format!("cannot write '{path}': {err}"))
+}
+
+fn execute_run(options: &RunOptions) -> Result<bool, String> {
+    let Program { mut chip8, rom, symbols } = load_program(&options.rom, options.quirks)?;
+    chip8.seed_rng(options.seed);
+    if options.coverage.is_some() {
+        chip8.coverage = Some(Coverage::new());
+    }
+
+    let mut runner = HeadlessRunner::new(chip8);
+    if let Some(ips) = options.ips {
+        runner.clock.instructions_per_second = ips;
+    }
+    if let Some(path) = &options.keys {
+        let text = fs::read_to_string(path).map_err(|err| format!("cannot read '{path}': {err}"))?;
+        runner.keys = KeyScript::parse(&text).map_err(|err| err.to_string())?;
+    }
+    if let Some(path) = &options.trace {
+        let file = File::create(path).map_err(|err| format!("cannot create '{path}': {err}"))?;
+        let mode = options.trace_last.map_or(TraceMode::Stream, TraceMode::Ring);
+        let mut tracer = Tracer::new(Box::new(BufWriter::new(file)), mode);
+        tracer.set_ranges(options.trace_ranges.clone());
+        runner.tracer = Some(tracer);
+    }
+    if options.profile.is_some() || options.profile_folded.is_some() {
+        runner.profiler = Some(Profiler::new());
+    }
+
+    let result = runner.run_frames(options.frames);
+    if let Err(err) = &result {
+        eprintln!("fault after {} frames: {err}", runner.frame);
+    }
+    if let (Some(tracer), Some(path)) = (runner.tracer.take(), &options.trace) {
+        tracer.finish().map_err(|err| format!("cannot write '{path}': {err}"))?;
+    }
+    if let Some(profiler) = &runner.profiler {
+        if let Some(path) = &options.profile {
+            write_report(path, |out| profiler.write_csv(out, &symbols))?;
+        }
+        if let Some(path) = &options.profile_folded {
+            write_report(path, |out| profiler.write_folded(out, &symbols))?;
+        }
+    }
+    if let Some(coverage) = &runner.chip8.coverage
+        && let Some(path) = &options.coverage
+    {
+        write_report(path, |out| coverage.write_listing(out, &rom, &symbols))?;
+        if let Some(lcov) = &options.coverage_lcov {
+            write_report(lcov, |out| coverage.write_lcov(out, &rom, &symbols, path))?;
+        }
+    }
+
+    if let Some(path) = &options.screenshot {
+        write_screenshot(&runner.chip8, path).map_err(|err| format!("cannot write '{path}': {err}"))?;
+    }
+    if let Some(path) = &options.dump_state {
+        fs::write(path, headless::state_json(&runner.chip8, runner.frame))
+            .map_err(|err| format!("cannot write '{path}': {err}"))?;
+    }
+
+    Ok(result.is_ok())
+}
+
+fn write_screenshot(chip8: &Chip8, path: &str) -> Result<(), Box<dyn std::error::Error>> {
+    let file = BufWriter::new(File::create(path)?);
+    headless::write_png(chip8, file)?;
+    Ok(())
+}
+
+struct DisasmOptions {
+    rom: String,
+    syntax: Syntax,
+    output: Option<String>,
+}
+
+fn parse_disasm_options(args: &[String]) -> Result<DisasmOptions, String> {
+    let mut syntax = Syntax::default();
+    let mut output = None;
+    let mut rom = None;
+
+    let mut args = args.iter();
+    while let Some(arg) = args.next() {
+        let mut value = || args.next().cloned().ok_or_else(|| format!("missing value for {arg}"));
+        match arg.as_str() {
+            "--syntax" => {
+                let id = value()?;
+                syntax = Syntax::from_id(&id).ok_or_else(|| format!("unknown syntax '{id}'"))?;
+            }
+            "--output" => output = Some(value()?),
+            _ if arg.starts_with("--") => return Err(format!("unknown option {arg}")),
+            _ if rom.is_none() => rom = Some(arg.clone()),
+            _ => return Err(format!("unexpected argument {arg}")),
+        }
+    }
+
+    Ok(DisasmOptions { rom: rom.ok_or("missing ROM path")?, syntax, output })
+}
+
+pub fn run_disasm(args: &[String]) -> ExitCode {
+    let options = match parse_disasm_options(args) {
+        Ok(options) => options,
+        Err(err) => {
+            eprintln!("error: {err}\n\n{USAGE}");
+            return ExitCode::FAILURE;
+        }
+    };
+
+    let rom = match fs::read(&options.rom) {
+        Ok(rom) => rom,
+        Err(err) => {
+            eprintln!("error: cannot read '{}': {err}", options.rom);
+            return ExitCode::FAILURE;
+        }
+    };
+
+    let listing = RomListing::analyze(&rom).render(options.syntax);
+    match &options.output {
+        Some(path) => {
+            if let Err(err) = fs::write(path, listing) {
+                eprintln!("error: cannot write '{path}': {err}");
+                return ExitCode::FAILURE;
+            }
+        }
+        None => print!("{listing}"),
+    }
+    ExitCode::SUCCESS
+}
+
+struct AsmOptions {
+    source: String,
+    output: String,
+}
+
+fn parse_asm_options(args: &[String]) -> Result<AsmOptions, String> {
+    let mut output = None;
+    let mut source = None;
+
+    let mut args = args.iter();
+    while let Some(arg) = args.next() {
+        let mut value = || args.next().cloned().ok_or_else(|| format!("missing value for {arg}"));
+        match arg.as_str() {
+            "--output" => output = Some(value()?),
+            _ if arg.starts_with("--") => return Err(format!("unknown option {arg}")),
+            _ if source.is_none() => source = Some(arg.clone()),
+            _ => return Err(format!("unexpected argument {arg}")),
+        }
+    }
+
+    let source = source.ok_or("missing source path")?;
+    let output = output.unwrap_or_else(|| Path::new(&source).with_extension("ch8").display().to_string());
+    Ok(AsmOptions { source, output })
+}
+
+pub fn run_asm(args: &[String]) -> ExitCode {
+    let AsmOptions { source, output } = match parse_asm_options(args) {
+        Ok(options) => options,
+        Err(err) => {
+            eprintln!("error: {err}\n\n{USAGE}");
+            return ExitCode::FAILURE;
+        }
+    };
+
+    let rom = match assembler::assemble_file(&source) {
+        Ok(rom) => rom,
+        Err(err) => {
+            eprintln!("error: {err}");
+            return ExitCode::FAILURE;
+        }
+    };
+    if let Err(err) = fs::write(&output, &rom) {
+        eprintln!("error: cannot write '{output}': {err}");
+        return ExitCode::FAILURE;
+    }
+    println!("wrote {} bytes to {output}", rom.len());
+    ExitCode::SUCCESS
+}
+
+struct DiffTraceOptions {
+    rom: String,
+    reference: String,
+    ips: Option<u32>,
+    quirks: QuirksPreset,
+    context: usize,
+}
+
+fn parse_diff_trace_options(args: &[String]) -> Result<DiffTraceOptions, String> {
+    let mut ips = None;
+    let mut quirks = QuirksPreset::default();
+    let mut context = 8;
+    let mut paths = Vec::new();
+
+    let mut args = args.iter();
+    while let Some(arg) = args.next() {
+        let mut value = || args.next().cloned().ok_or_else(|| format!("missing value for {arg}"));
+        match arg.as_str() {
+            "--ips" => ips = Some(parse_ips(&value()?)?),
+            "--quirks" => {
+                let id = value()?;
+                quirks = QuirksPreset::from_id(&id).ok_or_else(|| format!("unknown quirks preset '{id}'"))?;
+            }
+            "--context" => context = value()?.parse().map_err(|_| "invalid --context value")?,
+            _ if arg.starts_with("--") => return Err(format!("unknown option {arg}")),
+            _ if paths.len() < 2 => paths.push(arg.clone()),
+            _ => return Err(format!("unexpected argument {arg}")),
+        }
+    }
+
+    let [rom, reference] = <[String; 2]>::try_from(paths).map_err(|_| "expected a ROM and a reference trace")?;
+    Ok(DiffTraceOptions { rom, reference, ips, quirks, context })
+}
+
+pub fn run_diff_trace(args: &[String]) -> ExitCode {
+    let options = match parse_diff_trace_options(args) {
+        Ok(options) => options,
+        Err(err) => {
+            eprintln!("error: {err}\n\n{USAGE}");
+            return ExitCode::FAILURE;
+        }
+    };
+
+    match execute_diff_trace(&options) {
+        Ok(true) => ExitCode::SUCCESS,
+        Ok(false) => ExitCode::FAILURE,
+        Err(err) => {
+            eprintln!("error: {err}");
+            ExitCode::FAILURE
+        }
+    }
+}
+
+fn execute_diff_trace(options: &DiffTraceOptions) -> Result<bool, String> {
+    let text = fs::read_to_string(&options.reference)
+        .map_err(|err| format!("cannot read '{}': {err}", options.reference))?;
+    let reference = ReferenceState::parse_log(&text).map_err(|err| err.to_string())?;
+
+    let mut chip8 = load_program(&options.rom, options.quirks)?.chip8;
+    let mut clock = Clock::default();
+    if let Some(ips) = options.ips {
+        clock.instructions_per_second = ips;
+    }
+
+    let divergence = match tracediff::diff_trace(&mut chip8, &mut clock, reference, options.context) {
+        DiffOutcome::Matched { instructions } => {
+            println!("reference trace matched for {instructions} instructions");
+            return Ok(true);
+        }
+        DiffOutcome::Diverged(divergence) => divergence,
+    };
+
+    println!(
+        "diverged at cycle {} (reference line {}):",
+        divergence.actual.cycle, divergence.reference.line
+    );
+    for entry in &divergence.history {
+        println!("  {entry}");
+    }
+    println!("expected:\n  {}", divergence.reference.text);
+    println!("actual:\n  {}", divergence.actual);
+    for mismatch in &divergence.mismatches {
+        println!("  {:<8} expected {}, got {}", mismatch.field, mismatch.expected, mismatch.actual);
+    }
+    if let Some(err) = &divergence.fault {
+        println!("  emulator fault: {err}");
+    }
+    Ok(false)
+}
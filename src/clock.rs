@@ -0,0 +1,59 @@
+use std::time::Duration;
+
+pub const TIMER_FREQUENCY: u64 = 60;
+pub const DEFAULT_INSTRUCTIONS_PER_SECOND: u32 = 700;
+
+const NANOS_PER_SECOND: u128 = 1_000_000_000;
+const MAX_PENDING_FRAMES: u64 = 10;
+
+pub struct Clock {
+    pub instructions_per_second: u32,
+    elapsed_nanos: u128,
+    frames: u64,
+}
+
+impl Clock {
+    pub fn new(instructions_per_second: u32) -> Self {
+        Clock {
+            instructions_per_second,
+            elapsed_nanos: 0,
+            frames: 0,
+        }
+    }
+
+    pub fn accumulate(&mut self, elapsed: Duration) {
+        self.elapsed_nanos += elapsed.as_nanos();
+
+        let due = self.due_frames();
+        if due > self.frames + MAX_PENDING_FRAMES {
+            self.frames = due - MAX_PENDING_FRAMES;
+        }
+    }
+
+    pub fn next_frame(&mut self) -> Option<usize> {
+        if self.frames >= self.due_frames() {
+            return None;
+        }
+
+        let ips = self.instructions_per_second as u64;
+        let start = self.frames * ips / TIMER_FREQUENCY;
+        let end = (self.frames + 1) * ips / TIMER_FREQUENCY;
+        self.frames += 1;
+        Some((end - start) as usize)
+    }
+
+    pub fn virtual_frame(&mut self) -> usize {
+        self.elapsed_nanos = (self.frames as u128 + 1) * NANOS_PER_SECOND / TIMER_FREQUENCY as u128 + 1;
+        self.next_frame().unwrap_or(0)
+    }
+
+    fn due_frames(&self) -> u64 {
+        (self.elapsed_nanos * TIMER_FREQUENCY as u128 / NANOS_PER_SECOND) as u64
+    }
+}
+
+impl Default for Clock {
+    fn default() -> Self {
+        Clock::new(DEFAULT_INSTRUCTIONS_PER_SECOND)
+    }
+}
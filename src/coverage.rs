@@ -0,0 +1,189 @@
+use std::collections::BTreeMap;
+use std::io::{self, Write};
+use std::path::{Path, PathBuf};
+
+use crate::chip8::{AccessKind, CycleInfo};
+use crate::listing::{self, Gutter, RomListing, Syntax};
+use crate::symbols::SymbolTable;
+
+const ADDRESS_SPACE: usize = 0x10000;
+
+/// The tracefile written next to a coverage listing: `game.lst` gets `game.lcov.info`.
+/// The name never matches the listing's own, whatever its extension.
+pub fn lcov_path(listing: &Path) -> PathBuf {
+    let stem = listing.file_stem().unwrap_or_default().to_string_lossy();
+    listing.with_file_name(format!("{stem}.lcov.info"))
+}
+
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct BranchCoverage {
+    pub not_skipped: u64,
+    pub skipped: u64,
+}
+
+#[derive(Debug, Clone)]
+pub struct Coverage {
+    executed: Vec<u64>,
+    read: Vec<bool>,
+    branches: BTreeMap<u16, BranchCoverage>,
+}
+
+impl Default for Coverage {
+    fn default() -> Self {
+        Coverage {
+            executed: vec![0; ADDRESS_SPACE],
+            read: vec![false; ADDRESS_SPACE],
+            branches: BTreeMap::new(),
+        }
+    }
+}
+
+impl Coverage {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn record(&mut self, cycle: &CycleInfo, next_pc: u16) {
+        let count = &mut self.executed[cycle.pc as usize];
+        *count = count.saturating_add(1);
+        for access in cycle.accesses.iter().filter(|access| access.kind == AccessKind::Read) {
+            self.read[access.address % ADDRESS_SPACE] = true;
+        }
+        if listing::is_skip(cycle.opcode) {
+            let branch = self.branches.entry(cycle.pc).or_default();
+            if next_pc == cycle.pc.wrapping_add(2) {
+                branch.not_skipped += 1;
+            } else {
+                branch.skipped += 1;
+            }
+        }
+    }
+
+    pub fn executions(&self, address: u16) -> u64 {
+        self.executed[address as usize]
+    }
+
+    pub fn was_read(&self, address: u16) -> bool {
+        self.read[address as usize]
+    }
+
+    pub fn branch(&self, address: u16) -> Option<BranchCoverage> {
+        self.branches.get(&address).copied()
+    }
+
+    pub fn executed_addresses(&self) -> impl Iterator<Item = u16> + '_ {
+        self.executed.iter().enumerate().filter(|&(_, &count)| count > 0).map(|(address, _)| address as u16)
+    }
+
+    pub fn listing(&self, rom: &[u8], symbols: &SymbolTable) -> RomListing {
+        let mut listing = RomListing::analyze_with_entry_points(rom, self.executed_addresses());
+        listing.apply_symbols(symbols);
+        listing
+    }
+
+    fn branch_complete(&self, address: u16) -> bool {
+        self.branch(address).is_none_or(|branch| branch.skipped > 0 && branch.not_skipped > 0)
+    }
+
+    pub fn write_listing(&self, out: &mut dyn Write, rom: &[u8], symbols: &SymbolTable) -> io::Result<()> {
+        let listing = self.listing(rom, symbols);
+        let mut instructions = (0, 0);
+        let mut data = (0, 0);
+        let mut branches = (0, 0);
+
+        let text = listing.render_with_gutter(Syntax::Cowgod, |gutter| match gutter {
+            Gutter::Header | Gutter::Label { .. } => format!("{:17}| ", ""),
+            Gutter::Code { address, .. } => {
+                let count = self.executions(address);
+                instructions.0 += (count > 0) as usize;
+                instructions.1 += 1;
+                if self.branch(address).is_some() {
+                    branches.0 += self.branch_complete(address) as usize;
+                    branches.1 += 1;
+                }
+                let status = match count {
+                    0 => "#####".to_string(),
+                    _ if !self.branch_complete(address) => format!("{count}*"),
+                    _ => count.to_string(),
+                };
+                format!("{:04X} {:>12}| ", address, status)
+            }
+            Gutter::Data { address, length } => {
+                let read = (0..length).filter(|&offset| self.was_read(address.wrapping_add(offset as u16))).count();
+                data.0 += read;
+                data.1 += length;
+                let status = match read {
+                    0 => String::new(),
+                    _ if read == length => "read".to_string(),
+                    _ => format!("read {read}/{length}"),
+                };
+                format!("{:04X} {:>12}| ", address, status)
+            }
+        });
+
+        write!(out, "{}", text)?;
+        writeln!(out)?;
+        writeln!(out, "; {}/{} instructions executed", instructions.0, instructions.1)?;
+        writeln!(out, "; {}/{} data bytes read", data.0, data.1)?;
+        writeln!(out, "; {}/{} skips taken both ways", branches.0, branches.1)?;
+        writeln!(out, "; ##### never executed, * skip only ever went one way")
+    }
+
+    pub fn write_lcov(&self, out: &mut dyn Write, rom: &[u8], symbols: &SymbolTable, source: &str) -> io::Result<()> {
+        let listing = self.listing(rom, symbols);
+        let mut functions = Vec::new();
+        let mut lines = Vec::new();
+        let mut line = 0;
+        listing.render_with_gutter(Syntax::Cowgod, |gutter| {
+            line += 1;
+            match gutter {
+                Gutter::Label { address }
+                    if listing.is_code(address) && (listing.is_subroutine(address) || symbols.name(address).is_some()) =>
+                {
+                    functions.push((line, address));
+                }
+                Gutter::Code { address, opcode, .. } => lines.push((line, address, opcode)),
+                _ => {}
+            }
+            String::new()
+        });
+
+        writeln!(out, "TN:")?;
+        writeln!(out, "SF:{}", source)?;
+        for &(line, address) in &functions {
+            writeln!(out, "FN:{},{}", line, listing.labels()[&address])?;
+        }
+        for &(_, address) in &functions {
+            writeln!(out, "FNDA:{},{}", self.executions(address), listing.labels()[&address])?;
+        }
+        writeln!(out, "FNF:{}", functions.len())?;
+        writeln!(out, "FNH:{}", functions.iter().filter(|&&(_, address)| self.executions(address) > 0).count())?;
+
+        let mut branch_count = (0, 0);
+        for &(line, address, opcode) in &lines {
+            if !listing::is_skip(opcode) {
+                continue;
+            }
+            let branch = self.branch(address).unwrap_or_default();
+            let executed = self.executions(address) > 0;
+            for (index, taken) in [branch.not_skipped, branch.skipped].into_iter().enumerate() {
+                if executed {
+                    writeln!(out, "BRDA:{},0,{},{}", line, index, taken)?;
+                } else {
+                    writeln!(out, "BRDA:{},0,{},-", line, index)?;
+                }
+                branch_count.0 += (taken > 0) as usize;
+                branch_count.1 += 1;
+            }
+        }
+        writeln!(out, "BRF:{}", branch_count.1)?;
+        writeln!(out, "BRH:{}", branch_count.0)?;
+
+        for &(line, address, _) in &lines {
+            writeln!(out, "DA:{},{}", line, self.executions(address))?;
+        }
+        writeln!(out, "LF:{}", lines.len())?;
+        writeln!(out, "LH:{}", lines.iter().filter(|&&(_, address, _)| self.executions(address) > 0).count())?;
+        writeln!(out, "end_of_record")
+    }
+}
@@ -9,13 +9,25 @@ pub fn disassemble(opcode: u16) -> String {
         0x0 => match nn {
             0xE0 => "CLS".to_string(),
             0xEE => "RET".to_string(),
+            0xC0..=0xCF => format!("SCD  {}", n),
+            0xD0..=0xDF => format!("SCU  {}", n),
+            0xFB => "SCR".to_string(),
+            0xFC => "SCL".to_string(),
+            0xFD => "EXIT".to_string(),
+            0xFE => "LOW".to_string(),
+            0xFF => "HIGH".to_string(),
             _    => format!("SYS  {:#05X}", nnn),
         },
         0x1 => format!("JP   {:#05X}", nnn),
         0x2 => format!("CALL {:#05X}", nnn),
         0x3 => format!("SE   V{:X}, {:#04X}", x, nn),
         0x4 => format!("SNE  V{:X}, {:#04X}", x, nn),
-        0x5 => format!("SE   V{:X}, V{:X}", x, y),
+        0x5 => match n {
+            0x0 => format!("SE   V{:X}, V{:X}", x, y),
+            0x2 => format!("SAVE V{:X}, V{:X}", x, y),
+            0x3 => format!("LOAD V{:X}, V{:X}", x, y),
+            _   => "UNKNOWN 5..".to_string(),
+        },
         0x6 => format!("LD   V{:X}, {:#04X}", x, nn),
         0x7 => format!("ADD  V{:X}, {:#04X}", x, nn),
         0x8 => match n {
@@ -25,33 +37,125 @@ pub fn disassemble(opcode: u16) -> String {
             0x3 => format!("XOR  V{:X}, V{:X}", x, y),
             0x4 => format!("ADD  V{:X}, V{:X}", x, y),
             0x5 => format!("SUB  V{:X}, V{:X}", x, y),
-            0x6 => format!("SHR  V{:X}", x),
+            0x6 if y == 0 => format!("SHR  V{:X}", x),
+            0x6 => format!("SHR  V{:X}, V{:X}", x, y),
             0x7 => format!("SUBN V{:X}, V{:X}", x, y),
-            0xE => format!("SHL  V{:X}", x),
-            _   => format!("UNKNOWN 8.."),
+            0xE if y == 0 => format!("SHL  V{:X}", x),
+            0xE => format!("SHL  V{:X}, V{:X}", x, y),
+            _   => "UNKNOWN 8..".to_string(),
         },
         0x9 => format!("SNE  V{:X}, V{:X}", x, y),
         0xA => format!("LD   I, {:#05X}", nnn),
         0xB => format!("JP   V0, {:#05X}", nnn),
         0xC => format!("RND  V{:X}, {:#04X}", x, nn),
-        0xD => format!("DRW  V{:X}, V{:X}, {:X}", x, y, n),
+        0xD => format!("DRW  V{:X}, V{:X}, {}", x, y, n),
         0xE => match nn {
             0x9E => format!("SKP  V{:X}", x),
             0xA1 => format!("SKNP V{:X}", x),
-            _    => format!("UNKNOWN E.."),
+            _    => "UNKNOWN E..".to_string(),
         },
         0xF => match nn {
+            0x00 if x == 0 => "LD   I, LONG".to_string(),
+            0x01 => format!("PLANE {}", x),
+            0x02 if x == 0 => "AUDIO".to_string(),
             0x07 => format!("LD   V{:X}, DT", x),
             0x0A => format!("LD   V{:X}, K", x),
             0x15 => format!("LD   DT, V{:X}", x),
             0x18 => format!("LD   ST, V{:X}", x),
             0x1E => format!("ADD  I, V{:X}", x),
             0x29 => format!("LD   F, V{:X}", x),
+            0x30 => format!("LD   HF, V{:X}", x),
             0x33 => format!("LD   B, V{:X}", x),
+            0x3A => format!("PITCH V{:X}", x),
             0x55 => format!("LD   [I], V{:X}", x),
             0x65 => format!("LD   V{:X}, [I]", x),
-            _    => format!("UNKNOWN F.."),
+            0x75 => format!("LD   R, V{:X}", x),
+            0x85 => format!("LD   V{:X}, R", x),
+            _    => "UNKNOWN F..".to_string(),
         },
         _ => "UNKNOWN".to_string(),
     }
-}
\ No newline at end of file
+}
+
+pub fn disassemble_octo(opcode: u16) -> String {
+    let x   = ((opcode & 0x0F00) >> 8) as usize;
+    let y   = ((opcode & 0x00F0) >> 4) as usize;
+    let n   = (opcode & 0x000F) as u8;
+    let nn  = (opcode & 0x00FF) as u8;
+    let nnn = opcode & 0x0FFF;
+
+    let text = match (opcode & 0xF000) >> 12 {
+        0x0 if x == 0 => match nn {
+            0xE0 => "clear".to_string(),
+            0xEE => "return".to_string(),
+            0xC0..=0xCF => format!("scroll-down {}", n),
+            0xD0..=0xDF => format!("scroll-up {}", n),
+            0xFB => "scroll-right".to_string(),
+            0xFC => "scroll-left".to_string(),
+            0xFD => "exit".to_string(),
+            0xFE => "lores".to_string(),
+            0xFF => "hires".to_string(),
+            _    => String::new(),
+        },
+        0x1 => format!("jump {:#05X}", nnn),
+        0x2 => format!(":call {:#05X}", nnn),
+        0x3 => format!("if v{:x} != {:#04X} then", x, nn),
+        0x4 => format!("if v{:x} == {:#04X} then", x, nn),
+        0x5 => match n {
+            0x0 => format!("if v{:x} != v{:x} then", x, y),
+            0x2 => format!("save v{:x} - v{:x}", x, y),
+            0x3 => format!("load v{:x} - v{:x}", x, y),
+            _   => String::new(),
+        },
+        0x6 => format!("v{:x} := {:#04X}", x, nn),
+        0x7 => format!("v{:x} += {:#04X}", x, nn),
+        0x8 => match n {
+            0x0 => format!("v{:x} := v{:x}", x, y),
+            0x1 => format!("v{:x} |= v{:x}", x, y),
+            0x2 => format!("v{:x} &= v{:x}", x, y),
+            0x3 => format!("v{:x} ^= v{:x}", x, y),
+            0x4 => format!("v{:x} += v{:x}", x, y),
+            0x5 => format!("v{:x} -= v{:x}", x, y),
+            0x6 => format!("v{:x} >>= v{:x}", x, y),
+            0x7 => format!("v{:x} =- v{:x}", x, y),
+            0xE => format!("v{:x} <<= v{:x}", x, y),
+            _   => String::new(),
+        },
+        0x9 if n == 0 => format!("if v{:x} == v{:x} then", x, y),
+        0xA => format!("i := {:#05X}", nnn),
+        0xB => format!("jump0 {:#05X}", nnn),
+        0xC => format!("v{:x} := random {:#04X}", x, nn),
+        0xD => format!("sprite v{:x} v{:x} {}", x, y, n),
+        0xE => match nn {
+            0x9E => format!("if v{:x} -key then", x),
+            0xA1 => format!("if v{:x} key then", x),
+            _    => String::new(),
+        },
+        0xF => match nn {
+            0x00 if x == 0 => "i := long".to_string(),
+            0x01 => format!("plane {}", x),
+            0x02 if x == 0 => "audio".to_string(),
+            0x07 => format!("v{:x} := delay", x),
+            0x0A => format!("v{:x} := key", x),
+            0x15 => format!("delay := v{:x}", x),
+            0x18 => format!("buzzer := v{:x}", x),
+            0x1E => format!("i += v{:x}", x),
+            0x29 => format!("i := hex v{:x}", x),
+            0x30 => format!("i := bighex v{:x}", x),
+            0x33 => format!("bcd v{:x}", x),
+            0x3A => format!("pitch := v{:x}", x),
+            0x55 => format!("save v{:x}", x),
+            0x65 => format!("load v{:x}", x),
+            0x75 => format!("saveflags v{:x}", x),
+            0x85 => format!("loadflags v{:x}", x),
+            _    => String::new(),
+        },
+        _ => String::new(),
+    };
+
+    if text.is_empty() {
+        format!("{:#04X} {:#04X}", opcode >> 8, opcode & 0xFF)
+    } else {
+        text
+    }
+}
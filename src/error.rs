@@ -0,0 +1,50 @@
+use std::error::Error;
+use std::fmt;
+use std::io;
+
+use crate::octo::OctoError;
+
+#[derive(Debug)]
+pub enum Chip8Error {
+    RomRead { path: String, source: io::Error },
+    RomTooLarge { size: usize, capacity: usize },
+    Compile { path: String, source: OctoError },
+    UnknownOpcode { opcode: u16, address: u16 },
+    StackOverflow { address: u16 },
+    StackUnderflow { address: u16 },
+    MemoryOutOfBounds { address: usize, pc: u16 },
+    InvalidKey { key: u8, address: u16 },
+}
+
+impl fmt::Display for Chip8Error {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Chip8Error::RomRead { path, source } => write!(f, "cannot read ROM '{path}': {source}"),
+            Chip8Error::RomTooLarge { size, capacity } => {
+                write!(f, "ROM is {size} bytes but only {capacity} bytes fit in memory")
+            }
+            Chip8Error::Compile { path, source } => write!(f, "cannot compile '{path}': {source}"),
+            Chip8Error::UnknownOpcode { opcode, address } => {
+                write!(f, "unknown opcode {opcode:#06X} at {address:#06X}")
+            }
+            Chip8Error::StackOverflow { address } => write!(f, "stack overflow at {address:#06X}"),
+            Chip8Error::StackUnderflow { address } => write!(f, "stack underflow at {address:#06X}"),
+            Chip8Error::MemoryOutOfBounds { address, pc } => {
+                write!(f, "memory access out of bounds at {address:#06X} (PC {pc:#06X})")
+            }
+            Chip8Error::InvalidKey { key, address } => {
+                write!(f, "invalid key index {key:#04X} at {address:#06X}")
+            }
+        }
+    }
+}
+
+impl Error for Chip8Error {
+    fn source(&self) -> Option<&(dyn Error + 'static)> {
+        match self {
+            Chip8Error::RomRead { source, .. } => Some(source),
+            Chip8Error::Compile { source, .. } => Some(source),
+            _ => None,
+        }
+    }
+}
@@ -0,0 +1,275 @@
+use std::fmt;
+
+use crate::chip8::Chip8;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Register {
+    V(usize),
+    I,
+    Pc,
+    Sp,
+    Dt,
+    St,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum BinaryOp {
+    Or,
+    And,
+    Eq,
+    Ne,
+    Lt,
+    Le,
+    Gt,
+    Ge,
+    BitOr,
+    BitXor,
+    BitAnd,
+    Add,
+    Sub,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Expr {
+    Number(i64),
+    Register(Register),
+    Memory(Box<Expr>),
+    Not(Box<Expr>),
+    Binary(BinaryOp, Box<Expr>, Box<Expr>),
+}
+
+#[derive(Debug)]
+pub struct ExprError {
+    pub column: usize,
+    pub message: String,
+}
+
+impl fmt::Display for ExprError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "column {}: {}", self.column, self.message)
+    }
+}
+
+impl std::error::Error for ExprError {}
+
+#[derive(Debug, Clone, PartialEq)]
+enum Token {
+    Number(i64),
+    Ident(String),
+    Op(&'static str),
+}
+
+const OPERATORS: [&str; 19] = [
+    "||", "&&", "==", "!=", "<=", ">=", "<", ">", "|", "^", "&", "+", "-", "!", "(", ")", "[", "]", "=",
+];
+
+fn tokenize(text: &str) -> Result<Vec<(usize, Token)>, ExprError> {
+    let mut tokens = Vec::new();
+    let mut position = 0;
+
+    while position < text.len() {
+        let rest = &text[position..];
+        let c = rest.chars().next().unwrap();
+        if c.is_whitespace() {
+            position += c.len_utf8();
+            continue;
+        }
+
+        let column = position + 1;
+        if c.is_ascii_alphanumeric() || c == '_' {
+            let len = rest.find(|c: char| !c.is_ascii_alphanumeric() && c != '_').unwrap_or(rest.len());
+            let word = &rest[..len];
+            let token = if c.is_ascii_digit() {
+                let value = match word.strip_prefix("0x").or_else(|| word.strip_prefix("0X")) {
+                    Some(hex) => i64::from_str_radix(hex, 16),
+                    None => word.parse(),
+                };
+                Token::Number(value.map_err(|_| ExprError { column, message: format!("invalid number '{word}'") })?)
+            } else {
+                Token::Ident(word.to_ascii_uppercase())
+            };
+            tokens.push((column, token));
+            position += len;
+            continue;
+        }
+
+        let Some(op) = OPERATORS.iter().find(|op| rest.starts_with(**op)) else {
+            return Err(ExprError { column, message: format!("unexpected character '{c}'") });
+        };
+        if *op == "=" {
+            return Err(ExprError { column, message: "use `==` for comparison".to_string() });
+        }
+        tokens.push((column, Token::Op(op)));
+        position += op.len();
+    }
+
+    Ok(tokens)
+}
+
+struct Parser {
+    tokens: Vec<(usize, Token)>,
+    position: usize,
+    end_column: usize,
+}
+
+const PRECEDENCE: [&[(&str, BinaryOp)]; 7] = [
+    &[("||", BinaryOp::Or)],
+    &[("&&", BinaryOp::And)],
+    &[
+        ("==", BinaryOp::Eq),
+        ("!=", BinaryOp::Ne),
+        ("<=", BinaryOp::Le),
+        (">=", BinaryOp::Ge),
+        ("<", BinaryOp::Lt),
+        (">", BinaryOp::Gt),
+    ],
+    &[("|", BinaryOp::BitOr)],
+    &[("^", BinaryOp::BitXor)],
+    &[("&", BinaryOp::BitAnd)],
+    &[("+", BinaryOp::Add), ("-", BinaryOp::Sub)],
+];
+
+impl Parser {
+    fn column(&self) -> usize {
+        self.tokens.get(self.position).map_or(self.end_column, |(column, _)| *column)
+    }
+
+    fn error(&self, message: &str) -> ExprError {
+        ExprError { column: self.column(), message: message.to_string() }
+    }
+
+    fn peek_op(&self) -> Option<&'static str> {
+        match self.tokens.get(self.position) {
+            Some((_, Token::Op(op))) => Some(op),
+            _ => None,
+        }
+    }
+
+    fn expect(&mut self, op: &str) -> Result<(), ExprError> {
+        if self.peek_op() != Some(op) {
+            return Err(self.error(&format!("expected `{op}`")));
+        }
+        self.position += 1;
+        Ok(())
+    }
+
+    fn binary(&mut self, level: usize) -> Result<Expr, ExprError> {
+        if level == PRECEDENCE.len() {
+            return self.unary();
+        }
+
+        let mut left = self.binary(level + 1)?;
+        while let Some(op) = self.peek_op()
+            && let Some(&(_, binary)) = PRECEDENCE[level].iter().find(|(symbol, _)| *symbol == op)
+        {
+            self.position += 1;
+            let right = self.binary(level + 1)?;
+            left = Expr::Binary(binary, Box::new(left), Box::new(right));
+        }
+        Ok(left)
+    }
+
+    fn unary(&mut self) -> Result<Expr, ExprError> {
+        let Some((column, token)) = self.tokens.get(self.position).cloned() else {
+            return Err(self.error("unexpected end of expression"));
+        };
+        self.position += 1;
+
+        match token {
+            Token::Number(value) => Ok(Expr::Number(value)),
+            Token::Ident(name) => parse_register(&name)
+                .map(Expr::Register)
+                .ok_or_else(|| ExprError { column, message: format!("unknown register '{name}'") }),
+            Token::Op("!") => Ok(Expr::Not(Box::new(self.unary()?))),
+            Token::Op("-") => Ok(Expr::Binary(BinaryOp::Sub, Box::new(Expr::Number(0)), Box::new(self.unary()?))),
+            Token::Op("(") => {
+                let inner = self.binary(0)?;
+                self.expect(")")?;
+                Ok(inner)
+            }
+            Token::Op("[") => {
+                let address = self.binary(0)?;
+                self.expect("]")?;
+                Ok(Expr::Memory(Box::new(address)))
+            }
+            Token::Op(op) => Err(ExprError { column, message: format!("unexpected `{op}`") }),
+        }
+    }
+}
+
+fn parse_register(name: &str) -> Option<Register> {
+    match name {
+        "I" => Some(Register::I),
+        "PC" => Some(Register::Pc),
+        "SP" => Some(Register::Sp),
+        "DT" => Some(Register::Dt),
+        "ST" => Some(Register::St),
+        _ => {
+            let digit = name.strip_prefix('V')?;
+            let index = usize::from_str_radix(digit, 16).ok().filter(|_| digit.len() == 1)?;
+            Some(Register::V(index))
+        }
+    }
+}
+
+impl Expr {
+    pub fn parse(text: &str) -> Result<Expr, ExprError> {
+        let tokens = tokenize(text)?;
+        let mut parser = Parser { tokens, position: 0, end_column: text.len() + 1 };
+        let expr = parser.binary(0)?;
+        if parser.position < parser.tokens.len() {
+            return Err(parser.error("unexpected trailing input"));
+        }
+        Ok(expr)
+    }
+
+    pub fn eval(&self, chip8: &Chip8) -> i64 {
+        match self {
+            Expr::Number(value) => *value,
+            Expr::Register(register) => match register {
+                Register::V(index) => chip8.v[*index] as i64,
+                Register::I => chip8.i as i64,
+                Register::Pc => chip8.pc as i64,
+                Register::Sp => chip8.sp as i64,
+                Register::Dt => chip8.delay_timer as i64,
+                Register::St => chip8.sound_timer as i64,
+            },
+            Expr::Memory(address) => {
+                let address = address.eval(chip8);
+                usize::try_from(address)
+                    .ok()
+                    .and_then(|address| chip8.memory.get(address))
+                    .map_or(0, |&byte| byte as i64)
+            }
+            Expr::Not(inner) => (inner.eval(chip8) == 0) as i64,
+            Expr::Binary(op, left, right) => {
+                let left = left.eval(chip8);
+                if *op == BinaryOp::And && left == 0 {
+                    return 0;
+                }
+                if *op == BinaryOp::Or && left != 0 {
+                    return 1;
+                }
+                let right = right.eval(chip8);
+                match op {
+                    BinaryOp::Or | BinaryOp::And => (right != 0) as i64,
+                    BinaryOp::Eq => (left == right) as i64,
+                    BinaryOp::Ne => (left != right) as i64,
+                    BinaryOp::Lt => (left < right) as i64,
+                    BinaryOp::Le => (left <= right) as i64,
+                    BinaryOp::Gt => (left > right) as i64,
+                    BinaryOp::Ge => (left >= right) as i64,
+                    BinaryOp::BitOr => left | right,
+                    BinaryOp::BitXor => left ^ right,
+                    BinaryOp::BitAnd => left & right,
+                    BinaryOp::Add => left.wrapping_add(right),
+                    BinaryOp::Sub => left.wrapping_sub(right),
+                }
+            }
+        }
+    }
+
+    pub fn is_true(&self, chip8: &Chip8) -> bool {
+        self.eval(chip8) != 0
+    }
+}
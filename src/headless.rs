@@ -0,0 +1,170 @@
+use std::fmt;
+use std::fmt::Write;
+use std::io;
+
+use crate::chip8::{self, Chip8, KEYPAD_SIZE};
+use crate::clock::Clock;
+use crate::error::Chip8Error;
+use crate::profiler::Profiler;
+use crate::trace::Tracer;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct KeyEvent {
+    pub frame: u64,
+    pub key: usize,
+    pub pressed: bool,
+}
+
+#[derive(Debug)]
+pub struct KeyScriptError {
+    pub line: usize,
+    pub message: String,
+}
+
+impl fmt::Display for KeyScriptError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "key script line {}: {}", self.line, self.message)
+    }
+}
+
+impl std::error::Error for KeyScriptError {}
+
+#[derive(Debug, Default, Clone)]
+pub struct KeyScript {
+    events: Vec<KeyEvent>,
+}
+
+impl KeyScript {
+    pub fn parse(text: &str) -> Result<Self, KeyScriptError> {
+        let mut events = Vec::new();
+
+        for (index, line) in text.lines().enumerate() {
+            let line_number = index + 1;
+            let line = line.split('#').next().unwrap_or("").trim();
+            if line.is_empty() {
+                continue;
+            }
+
+            let error = |message: &str| KeyScriptError { line: line_number, message: message.to_string() };
+            let fields: Vec<&str> = line.split_whitespace().collect();
+            let [frame, key, action] = fields[..] else {
+                return Err(error("expected `<frame> <key> <down|up>`"));
+            };
+
+            let frame = frame.parse::<u64>().map_err(|_| error("invalid frame number"))?;
+            let key = usize::from_str_radix(key.trim_start_matches("0x"), 16)
+                .ok()
+                .filter(|&key| key < KEYPAD_SIZE)
+                .ok_or_else(|| error("key must be a hex digit 0-F"))?;
+            let pressed = match action {
+                "down" => true,
+                "up" => false,
+                _ => return Err(error("action must be `down` or `up`")),
+            };
+
+            events.push(KeyEvent { frame, key, pressed });
+        }
+
+        events.sort_by_key(|event| event.frame);
+        Ok(KeyScript { events })
+    }
+
+    pub fn apply(&self, frame: u64, chip8: &mut Chip8) {
+        for event in self.events.iter().filter(|event| event.frame == frame) {
+            chip8.keypad[event.key] = event.pressed as u8;
+        }
+    }
+}
+
+pub struct HeadlessRunner {
+    pub chip8: Chip8,
+    pub clock: Clock,
+    pub keys: KeyScript,
+    pub frame: u64,
+    pub tracer: Option<Tracer>,
+    pub profiler: Option<Profiler>,
+}
+
+impl HeadlessRunner {
+    pub fn new(chip8: Chip8) -> Self {
+        HeadlessRunner {
+            chip8,
+            clock: Clock::default(),
+            keys: KeyScript::default(),
+            frame: 0,
+            tracer: None,
+            profiler: None,
+        }
+    }
+
+    pub fn run_frame(&mut self) -> Result<(), Chip8Error> {
+        self.keys.apply(self.frame, &mut self.chip8);
+        for _ in 0..self.clock.virtual_frame() {
+            if let Some(tracer) = self.tracer.as_mut() {
+                tracer.record(&self.chip8);
+            }
+            if let Some(profiler) = self.profiler.as_mut() {
+                profiler.record(&self.chip8);
+            }
+            self.chip8.emulate_cycle()?;
+        }
+        self.chip8.tick_timers();
+        if let Some(profiler) = self.profiler.as_mut() {
+            profiler.end_frame();
+        }
+        self.frame += 1;
+        Ok(())
+    }
+
+    pub fn run_frames(&mut self, frames: u64) -> Result<(), Chip8Error> {
+        for _ in 0..frames {
+            self.run_frame()?;
+        }
+        Ok(())
+    }
+}
+
+pub fn state_json(chip8: &Chip8, frame: u64) -> String {
+    let list = |values: &mut dyn Iterator<Item = u32>| {
+        values.map(|value| value.to_string()).collect::<Vec<_>>().join(", ")
+    };
+
+    let mut json = String::new();
+    json.push_str("{\n");
+    let _ = writeln!(json, "  \"frame\": {frame},");
+    let _ = writeln!(json, "  \"pc\": {},", chip8.pc);
+    let _ = writeln!(json, "  \"i\": {},", chip8.i);
+    let _ = writeln!(json, "  \"sp\": {},", chip8.sp);
+    let _ = writeln!(json, "  \"v\": [{}],", list(&mut chip8.v.iter().map(|&v| v as u32)));
+    let _ = writeln!(json, "  \"stack\": [{}],", list(&mut chip8.stack.iter().map(|&v| v as u32)));
+    let _ = writeln!(json, "  \"delay_timer\": {},", chip8.delay_timer);
+    let _ = writeln!(json, "  \"sound_timer\": {},", chip8.sound_timer);
+    let _ = writeln!(json, "  \"hires\": {},", chip8.hires);
+    let _ = writeln!(json, "  \"halted\": {},", chip8.halted);
+    json.push_str("  \"display\": [\n");
+
+    let width = chip8.display_width();
+    let rows: Vec<String> = chip8
+        .display
+        .chunks(width)
+        .map(|row| {
+            let pixels: String = row.iter().map(|&pixel| char::from(b'0' + pixel)).collect();
+            format!("    \"{pixels}\"")
+        })
+        .collect();
+    json.push_str(&rows.join(",\n"));
+    json.push_str("\n  ]\n}\n");
+    json
+}
+
+pub fn write_png(chip8: &Chip8, out: impl io::Write) -> Result<(), png::EncodingError> {
+    let mut encoder = png::Encoder::new(out, chip8.display_width() as u32, chip8.display_height() as u32);
+    encoder.set_color(png::ColorType::Indexed);
+    encoder.set_depth(png::BitDepth::Eight);
+    encoder.set_palette(chip8::DISPLAY_PALETTE.concat());
+
+    let mut writer = encoder.write_header()?;
+    let pixels: Vec<u8> = chip8.display.iter().map(|&pixel| pixel & 0x3).collect();
+    writer.write_image_data(&pixels)?;
+    writer.finish()
+}
@@ -0,0 +1,192 @@
+use std::collections::VecDeque;
+
+use crate::chip8::{AUDIO_PATTERN_SIZE, Chip8, NUM_REGISTERS, RPL_FLAGS_SIZE, STACK_SIZE};
+
+pub const DEFAULT_JOURNAL_CAPACITY: usize = 20_000;
+
+#[derive(Debug, Clone, PartialEq)]
+struct CpuState {
+    v: [u8; NUM_REGISTERS],
+    i: u16,
+    pc: u16,
+    stack: [u16; STACK_SIZE],
+    sp: u8,
+    delay_timer: u8,
+    sound_timer: u8,
+    audio_pattern: Option<[u8; AUDIO_PATTERN_SIZE]>,
+    pitch: u8,
+    planes: u8,
+    hires: bool,
+    halted: bool,
+    vblank: bool,
+    rpl_flags: [u8; RPL_FLAGS_SIZE],
+}
+
+impl CpuState {
+    fn capture(chip8: &Chip8) -> Self {
+        CpuState {
+            v: chip8.v,
+            i: chip8.i,
+            pc: chip8.pc,
+            stack: chip8.stack,
+            sp: chip8.sp,
+            delay_timer: chip8.delay_timer,
+            sound_timer: chip8.sound_timer,
+            audio_pattern: chip8.audio_pattern,
+            pitch: chip8.pitch,
+            planes: chip8.planes,
+            hires: chip8.hires,
+            halted: chip8.halted,
+            vblank: chip8.vblank,
+            rpl_flags: chip8.rpl_flags,
+        }
+    }
+
+    fn restore(&self, chip8: &mut Chip8) {
+        chip8.v = self.v;
+        chip8.i = self.i;
+        chip8.pc = self.pc;
+        chip8.stack = self.stack;
+        chip8.sp = self.sp;
+        chip8.delay_timer = self.delay_timer;
+        chip8.sound_timer = self.sound_timer;
+        chip8.audio_pattern = self.audio_pattern;
+        chip8.pitch = self.pitch;
+        chip8.planes = self.planes;
+        chip8.hires = self.hires;
+        chip8.halted = self.halted;
+        chip8.vblank = self.vblank;
+        chip8.rpl_flags = self.rpl_flags;
+    }
+}
+
+#[derive(Debug, Clone)]
+pub struct JournalEntry {
+    cpu: CpuState,
+    memory: Vec<(usize, u8)>,
+    pixels: Vec<(usize, u8)>,
+    display: Option<Vec<u8>>,
+}
+
+impl JournalEntry {
+    pub fn pc(&self) -> u16 {
+        self.cpu.pc
+    }
+}
+
+#[derive(Debug, Clone, Default)]
+pub struct UndoJournal {
+    capacity: usize,
+    entries: VecDeque<JournalEntry>,
+    current: Option<JournalEntry>,
+}
+
+impl UndoJournal {
+    pub fn capacity(&self) -> usize {
+        self.capacity
+    }
+
+    pub fn set_capacity(&mut self, capacity: usize) {
+        self.capacity = capacity;
+        while self.entries.len() > capacity {
+            self.entries.pop_front();
+        }
+    }
+
+    pub fn is_enabled(&self) -> bool {
+        self.capacity > 0
+    }
+
+    pub fn len(&self) -> usize {
+        self.entries.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.entries.is_empty()
+    }
+
+    pub fn last(&self) -> Option<&JournalEntry> {
+        self.entries.back()
+    }
+
+    pub fn clear(&mut self) {
+        self.entries.clear();
+        self.current = None;
+    }
+
+    pub(crate) fn record_memory(&mut self, address: usize, old: u8) {
+        if let Some(entry) = self.current.as_mut() {
+            entry.memory.push((address, old));
+        }
+    }
+
+    pub(crate) fn record_pixel(&mut self, index: usize, old: u8) {
+        if let Some(entry) = self.current.as_mut()
+            && entry.display.is_none()
+        {
+            entry.pixels.push((index, old));
+        }
+    }
+
+    pub(crate) fn record_display(&mut self, display: &[u8]) {
+        if let Some(entry) = self.current.as_mut()
+            && entry.display.is_none()
+        {
+            entry.display = Some(display.to_vec());
+        }
+    }
+
+    pub(crate) fn discard(&mut self) {
+        self.current = None;
+    }
+}
+
+impl Chip8 {
+    pub(crate) fn journal_begin(&mut self) {
+        if self.journal.is_enabled() {
+            self.journal.current = Some(JournalEntry {
+                cpu: CpuState::capture(self),
+                memory: Vec::new(),
+                pixels: Vec::new(),
+                display: None,
+            });
+        }
+    }
+
+    pub(crate) fn journal_commit(&mut self) {
+        let Some(entry) = self.journal.current.take() else {
+            return;
+        };
+
+        let unchanged = entry.memory.is_empty()
+            && entry.pixels.is_empty()
+            && entry.display.is_none()
+            && entry.cpu == CpuState::capture(self);
+        if unchanged {
+            return;
+        }
+
+        if self.journal.entries.len() == self.journal.capacity {
+            self.journal.entries.pop_front();
+        }
+        self.journal.entries.push_back(entry);
+    }
+
+    pub fn step_back(&mut self) -> bool {
+        let Some(entry) = self.journal.entries.pop_back() else {
+            return false;
+        };
+
+        if let Some(display) = entry.display {
+            self.display = display;
+        }
+        for &(index, old) in entry.pixels.iter().rev() {
+            self.display[index] = old;
+        }
+        for &(address, old) in entry.memory.iter().rev() {
+            self.memory[address] = old;
+        }
+        entry.cpu.restore(self);
+        true
+    }
+}
@@ -0,0 +1,20 @@
+pub mod assembler;
+pub mod audio;
+pub mod breakpoints;
+pub mod chip8;
+pub mod clock;
+pub mod coverage;
+pub mod disasm;
+pub mod error;
+pub mod expr;
+pub mod headless;
+pub mod journal;
+pub mod listing;
+pub mod octo;
+pub mod profiler;
+pub mod quirks;
+pub mod rewind;
+pub mod savestate;
+pub mod symbols;
+pub mod trace;
+pub mod tracediff;
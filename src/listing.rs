@@ -0,0 +1,376 @@
+use std::collections::{BTreeMap, BTreeSet};
+use std::fmt::Write;
+
+use crate::chip8::PROGRAM_START;
+use crate::disasm;
+use crate::symbols::SymbolTable;
+
+const BYTES_PER_DATA_LINE: usize = 8;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum Syntax {
+    #[default]
+    Cowgod,
+    Octo,
+}
+
+impl Syntax {
+    pub fn id(self) -> &'static str {
+        match self {
+            Syntax::Cowgod => "cowgod",
+            Syntax::Octo => "octo",
+        }
+    }
+
+    pub fn from_id(id: &str) -> Option<Self> {
+        match id {
+            "cowgod" => Some(Syntax::Cowgod),
+            "octo" => Some(Syntax::Octo),
+            _ => None,
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord)]
+enum LabelKind {
+    Data,
+    Label,
+    Loop,
+    Sub,
+}
+
+impl LabelKind {
+    fn prefix(self) -> &'static str {
+        match self {
+            LabelKind::Data => "data",
+            LabelKind::Label => "label",
+            LabelKind::Loop => "loop",
+            LabelKind::Sub => "sub",
+        }
+    }
+}
+
+enum Line {
+    Instruction { address: u16, opcode: u16, long: Option<u16> },
+    Data { address: u16, bytes: Vec<u8> },
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Gutter {
+    Header,
+    Label { address: u16 },
+    Code { address: u16, opcode: u16, length: usize },
+    Data { address: u16, length: usize },
+}
+
+pub struct RomListing {
+    origin: u16,
+    rom: Vec<u8>,
+    instructions: BTreeSet<u16>,
+    subroutines: BTreeSet<u16>,
+    labels: BTreeMap<u16, String>,
+}
+
+pub fn instruction_length(opcode: u16) -> Option<u16> {
+    let x = (opcode & 0x0F00) >> 8;
+    let n = opcode & 0x000F;
+    let nn = opcode & 0x00FF;
+
+    let valid = match opcode >> 12 {
+        0x0 => x == 0 && matches!(nn, 0xE0 | 0xEE | 0xC0..=0xCF | 0xD0..=0xDF | 0xFB..=0xFF),
+        0x5 => matches!(n, 0x0 | 0x2 | 0x3),
+        0x8 => matches!(n, 0x0..=0x7 | 0xE),
+        0x9 => n == 0,
+        0xE => matches!(nn, 0x9E | 0xA1),
+        0xF => match nn {
+            0x00 => return (x == 0).then_some(4),
+            0x02 => x == 0,
+            0x01 | 0x07 | 0x0A | 0x15 | 0x18 | 0x1E | 0x29 | 0x30 | 0x33 | 0x3A | 0x55 | 0x65 | 0x75 | 0x85 => true,
+            _ => false,
+        },
+        _ => true,
+    };
+    valid.then_some(2)
+}
+
+pub fn is_skip(opcode: u16) -> bool {
+    match opcode >> 12 {
+        0x3 | 0x4 => true,
+        0x5 | 0x9 => opcode & 0xF == 0,
+        0xE => matches!(opcode & 0xFF, 0x9E | 0xA1),
+        _ => false,
+    }
+}
+
+impl RomListing {
+    pub fn analyze(rom: &[u8]) -> Self {
+        Self::analyze_with_entry_points(rom, [])
+    }
+
+    pub fn analyze_with_entry_points(rom: &[u8], entry_points: impl IntoIterator<Item = u16>) -> Self {
+        let origin = PROGRAM_START as u16;
+        let end = origin as usize + rom.len();
+        let fetch = |address: usize| -> Option<u16> {
+            let offset = address.checked_sub(origin as usize)?;
+            let bytes = rom.get(offset..offset + 2)?;
+            Some(((bytes[0] as u16) << 8) | bytes[1] as u16)
+        };
+        let in_rom = |address: u16| (origin as usize..end).contains(&(address as usize));
+
+        let mut instructions = BTreeSet::new();
+        let mut subroutines = BTreeSet::new();
+        let mut kinds: BTreeMap<u16, LabelKind> = BTreeMap::new();
+        let add_label = |kinds: &mut BTreeMap<u16, LabelKind>, address: u16, kind: LabelKind| {
+            if in_rom(address) {
+                let entry = kinds.entry(address).or_insert(kind);
+                *entry = (*entry).max(kind);
+            }
+        };
+
+        let mut pending: Vec<u16> = entry_points.into_iter().collect();
+        pending.reverse();
+        pending.push(origin);
+        while let Some(start) = pending.pop() {
+            let mut address = start;
+            while !instructions.contains(&address) {
+                let Some(opcode) = fetch(address as usize) else {
+                    break;
+                };
+                let Some(length) = instruction_length(opcode) else {
+                    break;
+                };
+                if length == 4 && fetch(address as usize + 2).is_none() {
+                    break;
+                }
+                instructions.insert(address);
+
+                let nnn = opcode & 0x0FFF;
+                let next = address.wrapping_add(length);
+                match opcode >> 12 {
+                    0x0 if matches!(opcode, 0x00EE | 0x00FD) => break,
+                    0x1 => {
+                        let kind = if nnn <= address { LabelKind::Loop } else { LabelKind::Label };
+                        add_label(&mut kinds, nnn, kind);
+                        pending.push(nnn);
+                        break;
+                    }
+                    0x2 => {
+                        subroutines.insert(nnn);
+                        add_label(&mut kinds, nnn, LabelKind::Sub);
+                        pending.push(nnn);
+                    }
+                    0xA => add_label(&mut kinds, nnn, LabelKind::Data),
+                    0xB => {
+                        add_label(&mut kinds, nnn, LabelKind::Label);
+                        break;
+                    }
+                    0xF if length == 4 => {
+                        if let Some(target) = fetch(address as usize + 2) {
+                            add_label(&mut kinds, target, LabelKind::Data);
+                        }
+                    }
+                    _ if is_skip(opcode) => {
+                        if let Some(skipped) = fetch(next as usize).and_then(instruction_length) {
+                            pending.push(next.wrapping_add(skipped));
+                        }
+                    }
+                    _ => {}
+                }
+                address = next;
+            }
+        }
+
+        let mut listing = RomListing { origin, rom: rom.to_vec(), instructions, subroutines, labels: BTreeMap::new() };
+        for (address, kind) in kinds {
+            let kind = if kind == LabelKind::Data && listing.instructions.contains(&address) {
+                LabelKind::Label
+            } else {
+                kind
+            };
+            listing.labels.insert(address, format!("{}_{:03X}", kind.prefix(), address));
+        }
+        listing.retain_placed_labels();
+        listing
+    }
+
+    pub fn apply_symbols(&mut self, symbols: &SymbolTable) {
+        let end = self.origin as usize + self.rom.len();
+        for address in self.origin..=(end.saturating_sub(1).min(u16::MAX as usize) as u16) {
+            if let Some(name) = symbols.name(address) {
+                self.labels.insert(address, name.to_string());
+            }
+        }
+        self.retain_placed_labels();
+    }
+
+    fn retain_placed_labels(&mut self) {
+        let mut placed = BTreeSet::new();
+        for line in self.lines() {
+            match line {
+                Line::Instruction { address, .. } => {
+                    placed.insert(address);
+                }
+                Line::Data { address, bytes } => {
+                    placed.extend((0..bytes.len()).map(|offset| (address as usize + offset) as u16));
+                }
+            }
+        }
+        self.labels.retain(|address, _| placed.contains(address));
+    }
+
+    pub fn labels(&self) -> &BTreeMap<u16, String> {
+        &self.labels
+    }
+
+    pub fn rom(&self) -> &[u8] {
+        &self.rom
+    }
+
+    pub fn contains(&self, address: u16) -> bool {
+        (self.origin as usize..self.origin as usize + self.rom.len()).contains(&(address as usize))
+    }
+
+    pub fn is_code(&self, address: u16) -> bool {
+        self.instructions.contains(&address)
+    }
+
+    pub fn is_subroutine(&self, address: u16) -> bool {
+        self.subroutines.contains(&address)
+    }
+
+    fn byte(&self, address: u16) -> u8 {
+        self.rom[(address - self.origin) as usize]
+    }
+
+    fn lines(&self) -> Vec<Line> {
+        let end = self.origin as usize + self.rom.len();
+        let mut lines = Vec::new();
+        let mut address = self.origin as usize;
+
+        while address < end {
+            if self.instructions.contains(&(address as u16)) {
+                let opcode = ((self.byte(address as u16) as u16) << 8) | self.byte(address as u16 + 1) as u16;
+                let length = instruction_length(opcode).unwrap_or(2) as usize;
+                let overlaps = (1..length).any(|offset| self.instructions.contains(&((address + offset) as u16)));
+                if !overlaps {
+                    let long = (length == 4).then(|| {
+                        ((self.byte(address as u16 + 2) as u16) << 8) | self.byte(address as u16 + 3) as u16
+                    });
+                    lines.push(Line::Instruction { address: address as u16, opcode, long });
+                    address += length;
+                    continue;
+                }
+            }
+
+            let start = address;
+            let mut bytes = Vec::new();
+            while address < end && bytes.len() < BYTES_PER_DATA_LINE {
+                let boundary = self.instructions.contains(&(address as u16)) || self.labels.contains_key(&(address as u16));
+                if address > start && boundary {
+                    break;
+                }
+                bytes.push(self.byte(address as u16));
+                address += 1;
+            }
+            lines.push(Line::Data { address: start as u16, bytes });
+        }
+        lines
+    }
+
+    fn target(&self, address: u16) -> String {
+        match self.labels.get(&address) {
+            Some(label) => label.clone(),
+            None => format!("{:#05X}", address),
+        }
+    }
+
+    fn cowgod_instruction(&self, opcode: u16, long: Option<u16>) -> String {
+        let nnn = opcode & 0x0FFF;
+        match opcode >> 12 {
+            0x1 => format!("JP   {}", self.target(nnn)),
+            0x2 => format!("CALL {}", self.target(nnn)),
+            0xA => format!("LD   I, {}", self.target(nnn)),
+            0xB => format!("JP   V0, {}", self.target(nnn)),
+            _ => match long {
+                Some(address) => format!("LD   I, LONG {}", self.target(address)),
+                None => disasm::disassemble(opcode),
+            },
+        }
+    }
+
+    fn octo_instruction(&self, opcode: u16, long: Option<u16>) -> String {
+        let nnn = opcode & 0x0FFF;
+        match opcode >> 12 {
+            0x1 => format!("jump {}", self.target(nnn)),
+            0x2 => match self.labels.get(&nnn) {
+                Some(label) => label.clone(),
+                None => format!(":call {:#05X}", nnn),
+            },
+            0xA => format!("i := {}", self.target(nnn)),
+            0xB => format!("jump0 {}", self.target(nnn)),
+            _ => match long {
+                Some(address) => format!("i := long {}", self.target(address)),
+                None => disasm::disassemble_octo(opcode),
+            },
+        }
+    }
+
+    pub fn render(&self, syntax: Syntax) -> String {
+        self.render_with_gutter(syntax, |_| String::new())
+    }
+
+    pub fn render_with_gutter(&self, syntax: Syntax, mut gutter: impl FnMut(Gutter) -> String) -> String {
+        let mut out = String::new();
+        for (kind, text) in self.rows(syntax) {
+            let gutter = gutter(kind);
+            match kind {
+                Gutter::Header | Gutter::Label { .. } => writeln!(out, "{}{}", gutter, text).unwrap(),
+                Gutter::Code { .. } | Gutter::Data { .. } => writeln!(out, "{}    {}", gutter, text).unwrap(),
+            }
+        }
+        out
+    }
+
+    pub fn rows(&self, syntax: Syntax) -> Vec<(Gutter, String)> {
+        let mut rows = Vec::new();
+        if syntax == Syntax::Octo {
+            rows.push((Gutter::Header, ": main".to_string()));
+        }
+
+        for line in self.lines() {
+            let address = match &line {
+                Line::Instruction { address, .. } | Line::Data { address, .. } => *address,
+            };
+            if let Some(label) = self.labels.get(&address) {
+                let text = match syntax {
+                    Syntax::Cowgod => format!("{}:", label),
+                    Syntax::Octo => format!(": {}", label),
+                };
+                rows.push((Gutter::Label { address }, text));
+            }
+            let gutter = match &line {
+                Line::Instruction { address, opcode, long } => Gutter::Code {
+                    address: *address,
+                    opcode: *opcode,
+                    length: if long.is_some() { 4 } else { 2 },
+                },
+                Line::Data { address, bytes } => Gutter::Data { address: *address, length: bytes.len() },
+            };
+
+            let text = match (&line, syntax) {
+                (Line::Instruction { opcode, long, .. }, Syntax::Cowgod) => self.cowgod_instruction(*opcode, *long),
+                (Line::Instruction { opcode, long, .. }, Syntax::Octo) => self.octo_instruction(*opcode, *long),
+                (Line::Data { bytes, .. }, Syntax::Cowgod) => {
+                    let bytes: Vec<String> = bytes.iter().map(|byte| format!("{:#04X}", byte)).collect();
+                    format!("db   {}", bytes.join(", "))
+                }
+                (Line::Data { bytes, .. }, Syntax::Octo) => {
+                    let bytes: Vec<String> = bytes.iter().map(|byte| format!("{:#04X}", byte)).collect();
+                    bytes.join(" ")
+                }
+            };
+            rows.push((gutter, text));
+        }
+        rows
+    }
+}
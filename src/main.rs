@@ -1,26 +1,58 @@
-#![cfg_attr(not(debug_assertions), windows_subsystem = "windows")]
-
+#[cfg(feature = "gui")]
 mod app;
-mod chip8;
-mod disasm;
+#[cfg(feature = "gui")]
+mod audio_device;
+mod cli;
+#[cfg(feature = "gui")]
 mod ui;
 
+#[cfg(feature = "gui")]
 use app::MyApp; 
+#[cfg(feature = "gui")]
 use eframe::egui;
 use std::env;
+use std::process::ExitCode;
+
+fn main() -> ExitCode {
+    let args: Vec<String> = env::args().skip(1).collect();
 
-fn main() -> Result<(), eframe::Error> {
-    let args: Vec<String> = env::args().collect();
-    let rom_path = args.get(1).map(|s| s.as_str());
+    match args.first().map(String::as_str) {
+        Some("run") => cli::run_headless(&args[1..]),
+        Some("disasm") => cli::run_disasm(&args[1..]),
+        Some("asm") => cli::run_asm(&args[1..]),
+        Some("diff-trace") => cli::run_diff_trace(&args[1..]),
+        Some("-h" | "--help") => {
+            println!("{}", cli::USAGE);
+            ExitCode::SUCCESS
+        }
+        rom_path => run_gui(rom_path),
+    }
+}
 
+#[cfg(feature = "gui")]
+fn run_gui(rom_path: Option<&str>) -> ExitCode {
     let options = eframe::NativeOptions {
         viewport: egui::ViewportBuilder::default().with_inner_size([1024.0, 512.0]),
         ..Default::default()
     };
     
-    eframe::run_native(
+    let result = eframe::run_native(
         "CHIP-8 Emulator",
         options,
         Box::new(|_cc| Ok(Box::new(MyApp::new(rom_path)))),
-    )
-}
\ No newline at end of file
+    );
+
+    match result {
+        Ok(()) => ExitCode::SUCCESS,
+        Err(err) => {
+            eprintln!("error: {err}");
+            ExitCode::FAILURE
+        }
+    }
+}
+
+#[cfg(not(feature = "gui"))]
+fn run_gui(_rom_path: Option<&str>) -> ExitCode {
+    eprintln!("error: built without the `gui` feature\n\n{}", cli::USAGE);
+    ExitCode::FAILURE
+}
@@ -0,0 +1,799 @@
+use std::collections::{BTreeMap, HashMap};
+use std::fmt;
+use std::fs;
+
+use crate::chip8::{Chip8, PROGRAM_START, XO_MEMORY_SIZE};
+use crate::error::Chip8Error;
+
+const MAX_MACRO_EXPANSIONS: usize = 10_000;
+
+#[derive(Debug)]
+pub struct OctoError {
+    pub line: usize,
+    pub message: String,
+}
+
+impl fmt::Display for OctoError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "line {}: {}", self.line, self.message)
+    }
+}
+
+impl std::error::Error for OctoError {}
+
+#[derive(Debug, Clone)]
+pub struct OctoProgram {
+    pub rom: Vec<u8>,
+    pub labels: BTreeMap<String, u16>,
+}
+
+#[derive(Debug, Clone)]
+struct Token {
+    text: String,
+    line: usize,
+}
+
+struct Macro {
+    args: Vec<String>,
+    body: Vec<Token>,
+}
+
+enum Control {
+    Loop { start: u16, breaks: Vec<u16> },
+    Begin { jump: u16 },
+    Else { jump: u16 },
+}
+
+#[derive(Clone, Copy)]
+enum Patch {
+    Address,
+    Long,
+    Unpack,
+}
+
+pub fn compile(source: &str) -> Result<OctoProgram, OctoError> {
+    let mut compiler = Compiler {
+        tokens: tokenize(source),
+        position: 0,
+        rom: Vec::new(),
+        here: PROGRAM_START as u16,
+        labels: BTreeMap::new(),
+        constants: HashMap::new(),
+        aliases: HashMap::new(),
+        macros: HashMap::new(),
+        protos: HashMap::new(),
+        control: Vec::new(),
+        expansions: 0,
+    };
+    compiler.compile()?;
+    Ok(OctoProgram { rom: compiler.rom, labels: compiler.labels })
+}
+
+impl Chip8 {
+    pub fn load_octo(&mut self, path: &str) -> Result<OctoProgram, Chip8Error> {
+        let source = fs::read_to_string(path)
+            .map_err(|source| Chip8Error::RomRead { path: path.to_string(), source })?;
+        let program = compile(&source).map_err(|source| Chip8Error::Compile { path: path.to_string(), source })?;
+        self.load_rom_bytes(&program.rom)?;
+        Ok(program)
+    }
+}
+
+pub fn is_octo_source(path: &str) -> bool {
+    path.ends_with(".8o")
+}
+
+fn tokenize(source: &str) -> Vec<Token> {
+    let mut tokens = Vec::new();
+    for (index, line) in source.lines().enumerate() {
+        let line_text = line.split('#').next().unwrap_or("");
+        for word in line_text.split_whitespace() {
+            tokens.push(Token { text: word.to_string(), line: index + 1 });
+        }
+    }
+    tokens
+}
+
+fn parse_number(text: &str) -> Option<i64> {
+    let (negative, digits) = match text.strip_prefix('-') {
+        Some(rest) => (true, rest),
+        None => (false, text),
+    };
+    let value = if let Some(hex) = digits.strip_prefix("0x").or_else(|| digits.strip_prefix("0X")) {
+        i64::from_str_radix(hex, 16).ok()?
+    } else if let Some(binary) = digits.strip_prefix("0b").or_else(|| digits.strip_prefix("0B")) {
+        i64::from_str_radix(binary, 2).ok()?
+    } else {
+        digits.parse().ok()?
+    };
+    Some(if negative { -value } else { value })
+}
+
+fn parse_register(text: &str) -> Option<u16> {
+    let digit = text.strip_prefix(['v', 'V'])?;
+    if digit.len() != 1 {
+        return None;
+    }
+    u16::from_str_radix(digit, 16).ok()
+}
+
+fn invert_skip(opcode: u16) -> u16 {
+    match opcode >> 12 {
+        0x3 => opcode ^ 0x7000,
+        0x4 => opcode ^ 0x7000,
+        0x5 => (opcode & 0x0FFF) | 0x9000,
+        0x9 => (opcode & 0x0FFF) | 0x5000,
+        0xE if opcode & 0xFF == 0x9E => (opcode & 0xFF00) | 0xA1,
+        0xE => (opcode & 0xFF00) | 0x9E,
+        _ => opcode,
+    }
+}
+
+struct Compiler {
+    tokens: Vec<Token>,
+    position: usize,
+    rom: Vec<u8>,
+    here: u16,
+    labels: BTreeMap<String, u16>,
+    constants: HashMap<String, f64>,
+    aliases: HashMap<String, u16>,
+    macros: HashMap<String, Macro>,
+    protos: HashMap<String, Vec<(u16, Patch, usize)>>,
+    control: Vec<Control>,
+    expansions: usize,
+}
+
+impl Compiler {
+    fn line(&self) -> usize {
+        self.tokens
+            .get(self.position.saturating_sub(1))
+            .or_else(|| self.tokens.last())
+            .map_or(0, |token| token.line)
+    }
+
+    fn error(&self, message: impl Into<String>) -> OctoError {
+        OctoError { line: self.line(), message: message.into() }
+    }
+
+    fn peek(&self) -> Option<&str> {
+        self.tokens.get(self.position).map(|token| token.text.as_str())
+    }
+
+    fn next(&mut self) -> Result<String, OctoError> {
+        let token = self.tokens.get(self.position).ok_or_else(|| self.error("unexpected end of file"))?;
+        self.position += 1;
+        Ok(token.text.clone())
+    }
+
+    fn expect(&mut self, expected: &str) -> Result<(), OctoError> {
+        let token = self.next()?;
+        if token != expected {
+            return Err(self.error(format!("expected '{expected}', found '{token}'")));
+        }
+        Ok(())
+    }
+
+    fn emit_byte(&mut self, byte: u8) -> Result<(), OctoError> {
+        if self.here == u16::MAX {
+            return Err(self.error("program does not fit in memory"));
+        }
+        let offset = self.here as usize - PROGRAM_START;
+        if self.rom.len() <= offset {
+            self.rom.resize(offset + 1, 0);
+        }
+        self.rom[offset] = byte;
+        self.here += 1;
+        Ok(())
+    }
+
+    fn emit(&mut self, opcode: u16) -> Result<u16, OctoError> {
+        let address = self.here;
+        self.emit_byte((opcode >> 8) as u8)?;
+        self.emit_byte(opcode as u8)?;
+        Ok(address)
+    }
+
+    fn patch(&mut self, address: u16, patch: Patch, value: u16) {
+        let offset = address as usize - PROGRAM_START;
+        match patch {
+            Patch::Address => {
+                self.rom[offset] = (self.rom[offset] & 0xF0) | ((value >> 8) as u8 & 0x0F);
+                self.rom[offset + 1] = value as u8;
+            }
+            Patch::Long => {
+                self.rom[offset] = (value >> 8) as u8;
+                self.rom[offset + 1] = value as u8;
+            }
+            Patch::Unpack => {
+                self.rom[offset + 1] = (self.rom[offset + 1] & 0xF0) | ((value >> 8) as u8 & 0x0F);
+                self.rom[offset + 3] = value as u8;
+            }
+        }
+    }
+
+    fn register(&mut self) -> Result<u16, OctoError> {
+        let token = self.next()?;
+        self.register_of(&token).ok_or_else(|| self.error(format!("expected a register, found '{token}'")))
+    }
+
+    fn register_of(&self, token: &str) -> Option<u16> {
+        parse_register(token).or_else(|| self.aliases.get(token).copied())
+    }
+
+    fn value_of(&self, token: &str) -> Option<i64> {
+        if let Some(value) = parse_number(token) {
+            return Some(value);
+        }
+        if let Some(value) = self.constants.get(token) {
+            return Some(value.floor() as i64);
+        }
+        self.labels.get(token).map(|&address| address as i64)
+    }
+
+    fn value(&mut self, min: i64, max: i64) -> Result<i64, OctoError> {
+        let token = self.next()?;
+        let value = self.value_of(&token).ok_or_else(|| self.error(format!("undefined name '{token}'")))?;
+        if !(min..=max).contains(&value) {
+            return Err(self.error(format!("value '{token}' is out of range")));
+        }
+        Ok(value)
+    }
+
+    fn byte(&mut self) -> Result<u16, OctoError> {
+        Ok((self.value(-128, 255)? & 0xFF) as u16)
+    }
+
+    fn nibble(&mut self) -> Result<u16, OctoError> {
+        Ok(self.value(0, 15)? as u16)
+    }
+
+    fn address_operand(&mut self, opcode: u16) -> Result<(), OctoError> {
+        let token = self.next()?;
+        let address = self.emit(opcode)?;
+        self.reference(&token, address, Patch::Address)
+    }
+
+    fn reference(&mut self, token: &str, address: u16, patch: Patch) -> Result<(), OctoError> {
+        let max = match patch {
+            Patch::Address | Patch::Unpack => 0xFFF,
+            Patch::Long => 0xFFFF,
+        };
+        if let Some(value) = self.value_of(token) {
+            if !(0..=max).contains(&value) {
+                return Err(self.error(format!("address '{token}' is out of range")));
+            }
+            self.patch(address, patch, value as u16);
+            return Ok(());
+        }
+        if parse_register(token).is_some() || token.starts_with(':') {
+            return Err(self.error(format!("expected an address, found '{token}'")));
+        }
+        let line = self.line();
+        self.protos.entry(token.to_string()).or_default().push((address, patch, line));
+        Ok(())
+    }
+
+    fn jump_target(&self, keyword: &str, target: u16) -> Result<u16, OctoError> {
+        if target > 0xFFF {
+            return Err(self.error(format!("'{keyword}' target {target:#06X} is beyond the 12-bit address range")));
+        }
+        Ok(target)
+    }
+
+    fn define_label(&mut self, name: String) -> Result<(), OctoError> {
+        if self.labels.contains_key(&name) || self.constants.contains_key(&name) {
+            return Err(self.error(format!("'{name}' is already defined")));
+        }
+        if parse_register(&name).is_some() || parse_number(&name).is_some() {
+            return Err(self.error(format!("'{name}' is not a valid label name")));
+        }
+        for (address, patch, _) in self.protos.remove(&name).unwrap_or_default() {
+            if !matches!(patch, Patch::Long) && self.here > 0xFFF {
+                return Err(self.error(format!("label '{name}' is beyond the 12-bit address range")));
+            }
+            self.patch(address, patch, self.here);
+        }
+        self.labels.insert(name, self.here);
+        Ok(())
+    }
+
+    fn compile(&mut self) -> Result<(), OctoError> {
+        let main_first = self.tokens.first().is_some_and(|token| token.text == ":")
+            && self.tokens.get(1).is_some_and(|token| token.text == "main");
+        let has_main = self.tokens.windows(2).any(|pair| pair[0].text == ":" && pair[1].text == "main");
+        if has_main && !main_first {
+            let address = self.emit(0x1000)?;
+            self.protos.entry("main".to_string()).or_default().push((address, Patch::Address, 1));
+        }
+
+        while self.position < self.tokens.len() {
+            self.statement()?;
+        }
+
+        if !self.control.is_empty() {
+            return Err(self.error("unterminated 'loop' or 'begin' at end of file"));
+        }
+        if let Some((name, uses)) = self.protos.iter().min_by_key(|(_, uses)| uses[0].2) {
+            return Err(OctoError { line: uses[0].2, message: format!("undefined label '{name}'") });
+        }
+        Ok(())
+    }
+
+    fn statement(&mut self) -> Result<(), OctoError> {
+        let token = self.next()?;
+
+        if let Some(register) = self.register_of(&token) {
+            return self.register_statement(register);
+        }
+
+        match token.as_str() {
+            ":" => {
+                let name = self.next()?;
+                self.define_label(name)?;
+            }
+            ":alias" => {
+                let name = self.next()?;
+                let register = self.register()?;
+                self.aliases.insert(name, register);
+            }
+            ":const" => {
+                let name = self.next()?;
+                let value = self.value(i64::MIN, i64::MAX)?;
+                self.constants.insert(name, value as f64);
+            }
+            ":calc" => {
+                let name = self.next()?;
+                let value = self.calc_block()?;
+                self.constants.insert(name, value);
+            }
+            ":byte" => {
+                let value = if self.peek() == Some("{") { self.calc_block()?.floor() as i64 } else { self.byte()? as i64 };
+                self.emit_byte(value as u8)?;
+            }
+            ":org" => {
+                let address = self.value(PROGRAM_START as i64, XO_MEMORY_SIZE as i64 - 1)?;
+                self.here = address as u16;
+            }
+            ":call" => self.address_operand(0x2000)?,
+            ":unpack" => {
+                let nibble = self.nibble()?;
+                let name = self.next()?;
+                let address = self.emit(0x6000 | (nibble << 4))?;
+                self.emit(0x6100)?;
+                self.reference(&name, address, Patch::Unpack)?;
+            }
+            ":macro" => self.define_macro()?,
+            ":breakpoint" | ":proto" => {
+                self.next()?;
+            }
+            ":monitor" => {
+                self.next()?;
+                self.next()?;
+            }
+            "return" | ";" => {
+                self.emit(0x00EE)?;
+            }
+            "clear" => {
+                self.emit(0x00E0)?;
+            }
+            "hires" => {
+                self.emit(0x00FF)?;
+            }
+            "lores" => {
+                self.emit(0x00FE)?;
+            }
+            "exit" => {
+                self.emit(0x00FD)?;
+            }
+            "scroll-right" => {
+                self.emit(0x00FB)?;
+            }
+            "scroll-left" => {
+                self.emit(0x00FC)?;
+            }
+            "scroll-down" => {
+                let n = self.nibble()?;
+                self.emit(0x00C0 | n)?;
+            }
+            "scroll-up" => {
+                let n = self.nibble()?;
+                self.emit(0x00D0 | n)?;
+            }
+            "audio" => {
+                self.emit(0xF002)?;
+            }
+            "plane" => {
+                let n = self.nibble()?;
+                self.emit(0xF001 | (n << 8))?;
+            }
+            "jump" => self.address_operand(0x1000)?,
+            "jump0" => self.address_operand(0xB000)?,
+            "native" => self.address_operand(0x0000)?,
+            "bcd" => {
+                let x = self.register()?;
+                self.emit(0xF033 | (x << 8))?;
+            }
+            "save" | "load" => {
+                let x = self.register()?;
+                if self.peek() == Some("-") {
+                    self.position += 1;
+                    let y = self.register()?;
+                    let base = if token == "save" { 0x5002 } else { 0x5003 };
+                    self.emit(base | (x << 8) | (y << 4))?;
+                } else {
+                    let base = if token == "save" { 0xF055 } else { 0xF065 };
+                    self.emit(base | (x << 8))?;
+                }
+            }
+            "saveflags" => {
+                let x = self.register()?;
+                self.emit(0xF075 | (x << 8))?;
+            }
+            "loadflags" => {
+                let x = self.register()?;
+                self.emit(0xF085 | (x << 8))?;
+            }
+            "sprite" => {
+                let x = self.register()?;
+                let y = self.register()?;
+                let n = self.nibble()?;
+                self.emit(0xD000 | (x << 8) | (y << 4) | n)?;
+            }
+            "delay" | "buzzer" | "pitch" => {
+                self.expect(":=")?;
+                let x = self.register()?;
+                let base = match token.as_str() {
+                    "delay" => 0xF015,
+                    "buzzer" => 0xF018,
+                    _ => 0xF03A,
+                };
+                self.emit(base | (x << 8))?;
+            }
+            "i" => self.i_statement()?,
+            "if" => self.if_statement()?,
+            "else" => match self.control.pop() {
+                Some(Control::Begin { jump }) => {
+                    let end_jump = self.emit(0x1000)?;
+                    let here = self.jump_target("else", self.here)?;
+                    self.patch(jump, Patch::Address, here);
+                    self.control.push(Control::Else { jump: end_jump });
+                }
+                _ => return Err(self.error("'else' without a matching 'begin'")),
+            },
+            "end" => match self.control.pop() {
+                Some(Control::Begin { jump } | Control::Else { jump }) => {
+                    let here = self.jump_target("end", self.here)?;
+                    self.patch(jump, Patch::Address, here);
+                }
+                _ => return Err(self.error("'end' without a matching 'begin'")),
+            },
+            "loop" => self.control.push(Control::Loop { start: self.here, breaks: Vec::new() }),
+            "while" => {
+                let skip = self.condition()?;
+                for opcode in &skip[..skip.len() - 1] {
+                    self.emit(*opcode)?;
+                }
+                self.emit(invert_skip(skip[skip.len() - 1]))?;
+                let jump = self.emit(0x1000)?;
+                match self.control.iter_mut().rev().find(|control| matches!(control, Control::Loop { .. })) {
+                    Some(Control::Loop { breaks, .. }) => breaks.push(jump),
+                    _ => return Err(self.error("'while' outside of a loop")),
+                }
+            }
+            "again" => match self.control.pop() {
+                Some(Control::Loop { start, breaks }) => {
+                    self.emit(0x1000 | self.jump_target("again", start)?)?;
+                    for jump in breaks {
+                        let here = self.jump_target("again", self.here)?;
+                        self.patch(jump, Patch::Address, here);
+                    }
+                }
+                _ => return Err(self.error("'again' without a matching 'loop'")),
+            },
+            _ => {
+                if let Some(value) = parse_number(&token) {
+                    if !(-128..=255).contains(&value) {
+                        return Err(self.error(format!("byte '{token}' is out of range")));
+                    }
+                    self.emit_byte(value as u8)?;
+                } else if self.macros.contains_key(&token) {
+                    self.expand_macro(&token)?;
+                } else if let Some(&value) = self.constants.get(&token) {
+                    self.emit_byte(value.floor() as i64 as u8)?;
+                } else if token.starts_with(':') || matches!(token.as_str(), "then" | "begin" | ":=" | "{" | "}") {
+                    return Err(self.error(format!("unexpected '{token}'")));
+                } else {
+                    let address = self.emit(0x2000)?;
+                    self.reference(&token, address, Patch::Address)?;
+                }
+            }
+        }
+        Ok(())
+    }
+
+    fn register_statement(&mut self, x: u16) -> Result<(), OctoError> {
+        let op = self.next()?;
+        let rhs = self.peek().unwrap_or("").to_string();
+        let y = self.register_of(&rhs);
+
+        let opcode = match (op.as_str(), y) {
+            (":=", Some(y)) => 0x8000 | (y << 4),
+            ("|=", Some(y)) => 0x8001 | (y << 4),
+            ("&=", Some(y)) => 0x8002 | (y << 4),
+            ("^=", Some(y)) => 0x8003 | (y << 4),
+            ("+=", Some(y)) => 0x8004 | (y << 4),
+            ("-=", Some(y)) => 0x8005 | (y << 4),
+            (">>=", Some(y)) => 0x8006 | (y << 4),
+            ("=-", Some(y)) => 0x8007 | (y << 4),
+            ("<<=", Some(y)) => 0x800E | (y << 4),
+            (":=", None) if rhs == "random" => {
+                self.position += 1;
+                0xC000 | self.byte()?
+            }
+            (":=", None) if rhs == "key" => 0xF00A,
+            (":=", None) if rhs == "delay" => 0xF007,
+            (":=", None) => 0x6000 | self.byte()?,
+            ("+=", None) => 0x7000 | self.byte()?,
+            ("-=", None) => 0x7000 | ((self.value(-255, 255)?.wrapping_neg() & 0xFF) as u16),
+            _ => return Err(self.error(format!("unknown operator '{op}' for a register"))),
+        };
+        if y.is_some() || (op == ":=" && matches!(rhs.as_str(), "key" | "delay")) {
+            self.position += 1;
+        }
+        self.emit(opcode | (x << 8))?;
+        Ok(())
+    }
+
+    fn i_statement(&mut self) -> Result<(), OctoError> {
+        let op = self.next()?;
+        match op.as_str() {
+            "+=" => {
+                let x = self.register()?;
+                self.emit(0xF01E | (x << 8))?;
+            }
+            ":=" => match self.peek() {
+                Some("hex") => {
+                    self.position += 1;
+                    let x = self.register()?;
+                    self.emit(0xF029 | (x << 8))?;
+                }
+                Some("bighex") => {
+                    self.position += 1;
+                    let x = self.register()?;
+                    self.emit(0xF030 | (x << 8))?;
+                }
+                Some("long") => {
+                    self.position += 1;
+                    let token = self.next()?;
+                    self.emit(0xF000)?;
+                    let address = self.emit(0x0000)?;
+                    self.reference(&token, address, Patch::Long)?;
+                }
+                _ => self.address_operand(0xA000)?,
+            },
+            _ => return Err(self.error(format!("unknown operator '{op}' for i"))),
+        }
+        Ok(())
+    }
+
+    fn if_statement(&mut self) -> Result<(), OctoError> {
+        let skip = self.condition()?;
+        let keyword = self.next()?;
+        let (last, rest) = skip.split_last().unwrap();
+        for opcode in rest {
+            self.emit(*opcode)?;
+        }
+        match keyword.as_str() {
+            "then" => {
+                self.emit(*last)?;
+            }
+            "begin" => {
+                self.emit(invert_skip(*last))?;
+                let jump = self.emit(0x1000)?;
+                self.control.push(Control::Begin { jump });
+            }
+            _ => return Err(self.error(format!("expected 'then' or 'begin', found '{keyword}'"))),
+        }
+        Ok(())
+    }
+
+    // Returns the instructions for a condition; the last one skips the next instruction when the condition is false.
+    fn condition(&mut self) -> Result<Vec<u16>, OctoError> {
+        let x = self.register()?;
+        let op = self.next()?;
+        if op == "key" {
+            return Ok(vec![0xE0A1 | (x << 8)]);
+        }
+        if op == "-key" {
+            return Ok(vec![0xE09E | (x << 8)]);
+        }
+
+        let rhs = self.next()?;
+        let operand = match self.register_of(&rhs) {
+            Some(y) => Ok(y),
+            None => {
+                let value = self.value_of(&rhs).ok_or_else(|| self.error(format!("undefined name '{rhs}'")))?;
+                if !(-128..=255).contains(&value) {
+                    return Err(self.error(format!("value '{rhs}' is out of range")));
+                }
+                Err((value & 0xFF) as u16)
+            }
+        };
+
+        let compare = |load_rhs_first: bool, skip_when_set: bool| -> Vec<u16> {
+            let mut code = Vec::new();
+            match (operand, load_rhs_first) {
+                (Ok(y), false) => code.extend([0x8F00 | (x << 4), 0x8F05 | (y << 4)]),
+                (Ok(y), true) => code.extend([0x8F00 | (y << 4), 0x8F05 | (x << 4)]),
+                (Err(n), false) => code.extend([0x6F00 | n, 0x8F07 | (x << 4)]),
+                (Err(n), true) => code.extend([0x6F00 | n, 0x8F05 | (x << 4)]),
+            }
+            code.push(if skip_when_set { 0x4F00 } else { 0x3F00 });
+            code
+        };
+
+        Ok(match (op.as_str(), operand) {
+            ("==", Ok(y)) => vec![0x9000 | (x << 8) | (y << 4)],
+            ("!=", Ok(y)) => vec![0x5000 | (x << 8) | (y << 4)],
+            ("==", Err(n)) => vec![0x4000 | (x << 8) | n],
+            ("!=", Err(n)) => vec![0x3000 | (x << 8) | n],
+            ("<", _) => compare(false, true),
+            (">=", _) => compare(false, false),
+            (">", _) => compare(true, true),
+            ("<=", _) => compare(true, false),
+            _ => return Err(self.error(format!("unknown comparison '{op}'"))),
+        })
+    }
+
+    fn define_macro(&mut self) -> Result<(), OctoError> {
+        let name = self.next()?;
+        let mut args = Vec::new();
+        loop {
+            let token = self.next()?;
+            if token == "{" {
+                break;
+            }
+            args.push(token);
+        }
+
+        let mut body = Vec::new();
+        let mut depth = 1;
+        loop {
+            let token = self.tokens.get(self.position).cloned().ok_or_else(|| self.error("unterminated macro"))?;
+            self.position += 1;
+            match token.text.as_str() {
+                "{" => depth += 1,
+                "}" => {
+                    depth -= 1;
+                    if depth == 0 {
+                        break;
+                    }
+                }
+                _ => {}
+            }
+            body.push(token);
+        }
+        self.macros.insert(name, Macro { args, body });
+        Ok(())
+    }
+
+    fn expand_macro(&mut self, name: &str) -> Result<(), OctoError> {
+        self.expansions += 1;
+        if self.expansions > MAX_MACRO_EXPANSIONS {
+            return Err(self.error(format!("too many expansions of macro '{name}'")));
+        }
+
+        let argc = self.macros[name].args.len();
+        let mut values = Vec::with_capacity(argc);
+        for _ in 0..argc {
+            values.push(self.next()?);
+        }
+
+        let line = self.line();
+        let definition = &self.macros[name];
+        let body: Vec<Token> = definition
+            .body
+            .iter()
+            .map(|token| {
+                let text = match definition.args.iter().position(|arg| *arg == token.text) {
+                    Some(index) => values[index].clone(),
+                    None => token.text.clone(),
+                };
+                Token { text, line }
+            })
+            .collect();
+        self.tokens.splice(self.position..self.position, body);
+        Ok(())
+    }
+
+    fn calc_block(&mut self) -> Result<f64, OctoError> {
+        self.expect("{")?;
+        let value = self.calc_expression()?;
+        self.expect("}")?;
+        Ok(value)
+    }
+
+    fn calc_expression(&mut self) -> Result<f64, OctoError> {
+        let left = self.calc_term()?;
+        let Some(op) = self.peek().map(str::to_string) else {
+            return Ok(left);
+        };
+        let binary: Option<fn(f64, f64) -> f64> = match op.as_str() {
+            "+" => Some(|a, b| a + b),
+            "-" => Some(|a, b| a - b),
+            "*" => Some(|a, b| a * b),
+            "/" => Some(|a, b| a / b),
+            "%" => Some(|a, b| a % b),
+            "&" => Some(|a, b| ((a as i64) & (b as i64)) as f64),
+            "|" => Some(|a, b| ((a as i64) | (b as i64)) as f64),
+            "^" => Some(|a, b| ((a as i64) ^ (b as i64)) as f64),
+            "<<" => Some(|a, b| ((a as i64) << (b as i64)) as f64),
+            ">>" => Some(|a, b| ((a as i64) >> (b as i64)) as f64),
+            "pow" => Some(f64::powf),
+            "min" => Some(f64::min),
+            "max" => Some(f64::max),
+            "<" => Some(|a, b| (a < b) as i64 as f64),
+            "<=" => Some(|a, b| (a <= b) as i64 as f64),
+            ">" => Some(|a, b| (a > b) as i64 as f64),
+            ">=" => Some(|a, b| (a >= b) as i64 as f64),
+            "==" => Some(|a, b| (a == b) as i64 as f64),
+            "!=" => Some(|a, b| (a != b) as i64 as f64),
+            _ => None,
+        };
+        match binary {
+            Some(apply) => {
+                self.position += 1;
+                let right = self.calc_expression()?;
+                Ok(apply(left, right))
+            }
+            None => Ok(left),
+        }
+    }
+
+    fn calc_term(&mut self) -> Result<f64, OctoError> {
+        let token = self.next()?;
+        let unary: Option<fn(f64) -> f64> = match token.as_str() {
+            "-" => Some(|a| -a),
+            "~" => Some(|a| !(a as i64) as f64),
+            "!" => Some(|a| (a == 0.0) as i64 as f64),
+            "sin" => Some(f64::sin),
+            "cos" => Some(f64::cos),
+            "tan" => Some(f64::tan),
+            "exp" => Some(f64::exp),
+            "log" => Some(f64::ln),
+            "abs" => Some(f64::abs),
+            "sqrt" => Some(f64::sqrt),
+            "sign" => Some(f64::signum),
+            "ceil" => Some(f64::ceil),
+            "floor" => Some(f64::floor),
+            _ => None,
+        };
+        if let Some(apply) = unary {
+            return Ok(apply(self.calc_term()?));
+        }
+
+        match token.as_str() {
+            "(" => {
+                let value = self.calc_expression()?;
+                self.expect(")")?;
+                Ok(value)
+            }
+            "@" => {
+                let address = self.calc_term()? as i64;
+                let offset = address - PROGRAM_START as i64;
+                Ok(usize::try_from(offset).ok().and_then(|offset| self.rom.get(offset)).map_or(0.0, |&byte| byte as f64))
+            }
+            "HERE" => Ok(self.here as f64),
+            "PI" => Ok(std::f64::consts::PI),
+            "E" => Ok(std::f64::consts::E),
+            _ => {
+                if let Some(&value) = self.constants.get(&token) {
+                    return Ok(value);
+                }
+                self.value_of(&token)
+                    .map(|value| value as f64)
+                    .ok_or_else(|| self.error(format!("undefined name '{token}' in :calc")))
+            }
+        }
+    }
+}
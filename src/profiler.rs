@@ -0,0 +1,255 @@
+use std::collections::{HashMap, VecDeque};
+use std::io::{self, Write};
+
+use crate::chip8::{Chip8, PROGRAM_START};
+use crate::listing;
+use crate::symbols::SymbolTable;
+
+const ADDRESS_SPACE: usize = 0x10000;
+pub const FRAME_HISTORY: usize = 120;
+
+pub fn opcode_class(opcode: u16) -> &'static str {
+    if listing::instruction_length(opcode).is_none() {
+        return "????";
+    }
+    let n = opcode & 0x000F;
+    let nn = opcode & 0x00FF;
+    match opcode >> 12 {
+        0x0 => match nn {
+            0xE0 => "00E0",
+            0xEE => "00EE",
+            0xC0..=0xCF => "00CN",
+            0xD0..=0xDF => "00DN",
+            0xFB => "00FB",
+            0xFC => "00FC",
+            0xFD => "00FD",
+            0xFE => "00FE",
+            _ => "00FF",
+        },
+        0x1 => "1NNN",
+        0x2 => "2NNN",
+        0x3 => "3XNN",
+        0x4 => "4XNN",
+        0x5 => match n {
+            0x0 => "5XY0",
+            0x2 => "5XY2",
+            _ => "5XY3",
+        },
+        0x6 => "6XNN",
+        0x7 => "7XNN",
+        0x8 => match n {
+            0x0 => "8XY0",
+            0x1 => "8XY1",
+            0x2 => "8XY2",
+            0x3 => "8XY3",
+            0x4 => "8XY4",
+            0x5 => "8XY5",
+            0x6 => "8XY6",
+            0x7 => "8XY7",
+            _ => "8XYE",
+        },
+        0x9 => "9XY0",
+        0xA => "ANNN",
+        0xB => "BNNN",
+        0xC => "CXNN",
+        0xD => "DXYN",
+        0xE => match nn {
+            0x9E => "EX9E",
+            _ => "EXA1",
+        },
+        _ => match nn {
+            0x00 => "F000",
+            0x01 => "FN01",
+            0x02 => "F002",
+            0x07 => "FX07",
+            0x0A => "FX0A",
+            0x15 => "FX15",
+            0x18 => "FX18",
+            0x1E => "FX1E",
+            0x29 => "FX29",
+            0x30 => "FX30",
+            0x33 => "FX33",
+            0x3A => "FX3A",
+            0x55 => "FX55",
+            0x65 => "FX65",
+            0x75 => "FX75",
+            _ => "FX85",
+        },
+    }
+}
+
+pub fn subroutine_name(address: u16, symbols: &SymbolTable) -> String {
+    match symbols.name(address) {
+        Some(name) => name.to_string(),
+        None if address as usize == PROGRAM_START => "main".to_string(),
+        None => format!("sub_{:03X}", address),
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct SubroutineCycles {
+    pub address: u16,
+    pub exclusive: u64,
+    pub inclusive: u64,
+}
+
+pub struct Profiler {
+    total: u64,
+    address_counts: Vec<u64>,
+    opcodes: Vec<u16>,
+    class_counts: HashMap<&'static str, u64>,
+    stacks: HashMap<Vec<u16>, u64>,
+    frame_cycles: VecDeque<u64>,
+    current_frame: u64,
+}
+
+impl Default for Profiler {
+    fn default() -> Self {
+        Profiler {
+            total: 0,
+            address_counts: vec![0; ADDRESS_SPACE],
+            opcodes: vec![0; ADDRESS_SPACE],
+            class_counts: HashMap::new(),
+            stacks: HashMap::new(),
+            frame_cycles: VecDeque::with_capacity(FRAME_HISTORY),
+            current_frame: 0,
+        }
+    }
+}
+
+impl Profiler {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn reset(&mut self) {
+        *self = Self::default();
+    }
+
+    pub fn total(&self) -> u64 {
+        self.total
+    }
+
+    pub fn record(&mut self, chip8: &Chip8) {
+        let pc = chip8.pc as usize;
+        let Some(&[hi, lo]) = chip8.memory.get(pc..pc + 2) else {
+            return;
+        };
+        if chip8.halted {
+            return;
+        }
+        let opcode = ((hi as u16) << 8) | lo as u16;
+
+        self.total += 1;
+        self.current_frame += 1;
+        self.address_counts[pc] += 1;
+        self.opcodes[pc] = opcode;
+        *self.class_counts.entry(opcode_class(opcode)).or_default() += 1;
+        *self.stacks.entry(call_path(chip8)).or_default() += 1;
+    }
+
+    pub fn end_frame(&mut self) {
+        if self.frame_cycles.len() == FRAME_HISTORY {
+            self.frame_cycles.pop_front();
+        }
+        self.frame_cycles.push_back(self.current_frame);
+        self.current_frame = 0;
+    }
+
+    pub fn frame_cycles(&self) -> impl Iterator<Item = u64> + '_ {
+        self.frame_cycles.iter().copied()
+    }
+
+    pub fn hot_addresses(&self) -> Vec<(u16, u16, u64)> {
+        let mut hot: Vec<(u16, u16, u64)> = self
+            .address_counts
+            .iter()
+            .enumerate()
+            .filter(|&(_, &count)| count > 0)
+            .map(|(address, &count)| (address as u16, self.opcodes[address], count))
+            .collect();
+        hot.sort_by(|a, b| b.2.cmp(&a.2).then(a.0.cmp(&b.0)));
+        hot
+    }
+
+    pub fn opcode_classes(&self) -> Vec<(&'static str, u64)> {
+        let mut classes: Vec<(&'static str, u64)> = self.class_counts.iter().map(|(&class, &count)| (class, count)).collect();
+        classes.sort_by(|a, b| b.1.cmp(&a.1).then(a.0.cmp(b.0)));
+        classes
+    }
+
+    pub fn subroutines(&self) -> Vec<SubroutineCycles> {
+        let mut cycles: HashMap<u16, SubroutineCycles> = HashMap::new();
+        for (path, &count) in &self.stacks {
+            for (depth, &address) in path.iter().enumerate() {
+                let entry = cycles.entry(address).or_insert(SubroutineCycles { address, exclusive: 0, inclusive: 0 });
+                if !path[..depth].contains(&address) {
+                    entry.inclusive += count;
+                }
+                if depth == path.len() - 1 {
+                    entry.exclusive += count;
+                }
+            }
+        }
+        let mut cycles: Vec<SubroutineCycles> = cycles.into_values().collect();
+        cycles.sort_by(|a, b| b.exclusive.cmp(&a.exclusive).then(a.address.cmp(&b.address)));
+        cycles
+    }
+
+    pub fn write_csv(&self, out: &mut dyn Write, symbols: &SymbolTable) -> io::Result<()> {
+        writeln!(out, "address,symbol,opcode,class,instruction,count,percent")?;
+        for (address, opcode, count) in self.hot_addresses() {
+            writeln!(
+                out,
+                "{:#06X},{},{:04X},{},{},{},{:.3}",
+                address,
+                csv_field(&symbols.describe(address).unwrap_or_default()),
+                opcode,
+                opcode_class(opcode),
+                csv_field(&symbols.disassemble(opcode)),
+                count,
+                self.percent(count)
+            )?;
+        }
+        Ok(())
+    }
+
+    pub fn write_folded(&self, out: &mut dyn Write, symbols: &SymbolTable) -> io::Result<()> {
+        let mut lines: Vec<(String, u64)> = self
+            .stacks
+            .iter()
+            .map(|(path, &count)| {
+                // Folded stacks separate frames with `;` and the count with a space.
+                let names: Vec<String> = path
+                    .iter()
+                    .map(|&address| subroutine_name(address, symbols).replace([';', ' '], "_"))
+                    .collect();
+                (names.join(";"), count)
+            })
+            .collect();
+        lines.sort();
+        for (path, count) in lines {
+            writeln!(out, "{} {}", path, count)?;
+        }
+        Ok(())
+    }
+
+    pub fn percent(&self, count: u64) -> f64 {
+        if self.total == 0 { 0.0 } else { count as f64 * 100.0 / self.total as f64 }
+    }
+}
+
+fn csv_field(text: &str) -> String {
+    format!("\"{}\"", text.replace('"', "\"\""))
+}
+
+fn call_path(chip8: &Chip8) -> Vec<u16> {
+    let mut path = vec![PROGRAM_START as u16];
+    for &call in chip8.stack.iter().take(chip8.sp as usize) {
+        let call = call as usize;
+        if let Some(&[hi, lo]) = chip8.memory.get(call..call + 2) {
+            path.push((((hi as u16) << 8) | lo as u16) & 0x0FFF);
+        }
+    }
+    path
+}
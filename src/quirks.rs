@@ -0,0 +1,156 @@
+use crate::chip8::{MEMORY_SIZE, XO_MEMORY_SIZE};
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Quirks {
+    pub shift_uses_vy: bool,
+    pub load_store_increments_i: bool,
+    pub jump_uses_vx: bool,
+    pub vf_reset: bool,
+    pub clip_sprites: bool,
+    pub display_wait: bool,
+    pub lores_big_sprites: bool,
+}
+
+impl Quirks {
+    pub fn to_bits(self) -> u8 {
+        [
+            self.shift_uses_vy,
+            self.load_store_increments_i,
+            self.jump_uses_vx,
+            self.vf_reset,
+            self.clip_sprites,
+            self.display_wait,
+            self.lores_big_sprites,
+        ]
+        .iter()
+        .enumerate()
+        .fold(0, |bits, (index, &flag)| bits | ((flag as u8) << index))
+    }
+
+    pub fn from_bits(bits: u8) -> Self {
+        let flag = |index: u8| bits & (1 << index) != 0;
+        Quirks {
+            shift_uses_vy: flag(0),
+            load_store_increments_i: flag(1),
+            jump_uses_vx: flag(2),
+            vf_reset: flag(3),
+            clip_sprites: flag(4),
+            display_wait: flag(5),
+            lores_big_sprites: flag(6),
+        }
+    }
+
+    pub fn describe(self) -> &'static str {
+        QuirksPreset::ALL
+            .into_iter()
+            .find(|preset| preset.quirks() == self)
+            .map_or("custom", QuirksPreset::name)
+    }
+}
+
+impl Default for Quirks {
+    fn default() -> Self {
+        QuirksPreset::default().quirks()
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum QuirksPreset {
+    #[default]
+    Modern,
+    CosmacVip,
+    Chip48,
+    SuperChip,
+    XoChip,
+}
+
+impl QuirksPreset {
+    pub const ALL: [QuirksPreset; 5] = [
+        QuirksPreset::Modern,
+        QuirksPreset::CosmacVip,
+        QuirksPreset::Chip48,
+        QuirksPreset::SuperChip,
+        QuirksPreset::XoChip,
+    ];
+
+    pub fn name(self) -> &'static str {
+        match self {
+            QuirksPreset::Modern => "Modern",
+            QuirksPreset::CosmacVip => "COSMAC VIP",
+            QuirksPreset::Chip48 => "CHIP-48",
+            QuirksPreset::SuperChip => "SUPER-CHIP 1.1",
+            QuirksPreset::XoChip => "XO-CHIP",
+        }
+    }
+
+    pub fn id(self) -> &'static str {
+        match self {
+            QuirksPreset::Modern => "modern",
+            QuirksPreset::CosmacVip => "vip",
+            QuirksPreset::Chip48 => "chip48",
+            QuirksPreset::SuperChip => "schip",
+            QuirksPreset::XoChip => "xochip",
+        }
+    }
+
+    pub fn from_id(id: &str) -> Option<QuirksPreset> {
+        QuirksPreset::ALL.into_iter().find(|preset| preset.id() == id)
+    }
+
+    pub fn memory_size(self) -> usize {
+        match self {
+            QuirksPreset::XoChip => XO_MEMORY_SIZE,
+            _ => MEMORY_SIZE,
+        }
+    }
+
+    pub fn quirks(self) -> Quirks {
+        match self {
+            QuirksPreset::Modern => Quirks {
+                shift_uses_vy: false,
+                load_store_increments_i: false,
+                jump_uses_vx: false,
+                vf_reset: false,
+                clip_sprites: false,
+                display_wait: false,
+                lores_big_sprites: true,
+            },
+            QuirksPreset::CosmacVip => Quirks {
+                shift_uses_vy: true,
+                load_store_increments_i: true,
+                jump_uses_vx: false,
+                vf_reset: true,
+                clip_sprites: true,
+                display_wait: true,
+                lores_big_sprites: false,
+            },
+            QuirksPreset::Chip48 => Quirks {
+                shift_uses_vy: false,
+                load_store_increments_i: false,
+                jump_uses_vx: true,
+                vf_reset: false,
+                clip_sprites: true,
+                display_wait: false,
+                lores_big_sprites: false,
+            },
+            QuirksPreset::SuperChip => Quirks {
+                shift_uses_vy: false,
+                load_store_increments_i: false,
+                jump_uses_vx: true,
+                vf_reset: false,
+                clip_sprites: true,
+                display_wait: false,
+                lores_big_sprites: true,
+            },
+            QuirksPreset::XoChip => Quirks {
+                shift_uses_vy: true,
+                load_store_increments_i: true,
+                jump_uses_vx: false,
+                vf_reset: false,
+                clip_sprites: false,
+                display_wait: false,
+                lores_big_sprites: true,
+            },
+        }
+    }
+}
@@ -0,0 +1,173 @@
+use std::collections::VecDeque;
+
+use crate::chip8::Chip8;
+
+pub const DEFAULT_REWIND_BUDGET: usize = 16 * 1024 * 1024;
+
+const DELTA_XOR: u8 = 0;
+const DELTA_FULL: u8 = 1;
+
+pub struct RewindBuffer {
+    budget: usize,
+    latest: Option<Vec<u8>>,
+    deltas: VecDeque<Vec<u8>>,
+    used: usize,
+}
+
+impl RewindBuffer {
+    pub fn new(budget: usize) -> Self {
+        RewindBuffer {
+            budget,
+            latest: None,
+            deltas: VecDeque::new(),
+            used: 0,
+        }
+    }
+
+    pub fn budget(&self) -> usize {
+        self.budget
+    }
+
+    pub fn set_budget(&mut self, budget: usize) {
+        self.budget = budget;
+        self.trim();
+    }
+
+    pub fn memory_used(&self) -> usize {
+        self.used
+    }
+
+    pub fn len(&self) -> usize {
+        self.latest.as_ref().map_or(0, |_| self.deltas.len() + 1)
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.latest.is_none()
+    }
+
+    pub fn clear(&mut self) {
+        self.latest = None;
+        self.deltas.clear();
+        self.used = 0;
+    }
+
+    pub fn push(&mut self, chip8: &Chip8) {
+        let snapshot = chip8.save_state();
+
+        if let Some(previous) = self.latest.take() {
+            let delta = encode_delta(&snapshot, &previous);
+            self.used += delta.len();
+            self.used -= previous.len();
+            self.deltas.push_back(delta);
+        }
+
+        self.used += snapshot.len();
+        self.latest = Some(snapshot);
+        self.trim();
+    }
+
+    pub fn rewind(&mut self) -> Option<Chip8> {
+        let delta = self.deltas.pop_back()?;
+        let latest = self.latest.as_mut()?;
+
+        let previous = apply_delta(latest, &delta);
+        self.used -= delta.len();
+        self.used -= latest.len();
+        self.used += previous.len();
+        *latest = previous;
+
+        Chip8::from_state(latest).ok()
+    }
+
+    fn trim(&mut self) {
+        while self.used > self.budget {
+            let Some(oldest) = self.deltas.pop_front() else {
+                break;
+            };
+            self.used -= oldest.len();
+        }
+    }
+}
+
+impl Default for RewindBuffer {
+    fn default() -> Self {
+        RewindBuffer::new(DEFAULT_REWIND_BUDGET)
+    }
+}
+
+fn write_varint(out: &mut Vec<u8>, mut value: usize) {
+    while value >= 0x80 {
+        out.push((value as u8) | 0x80);
+        value >>= 7;
+    }
+    out.push(value as u8);
+}
+
+fn read_varint(data: &[u8], position: &mut usize) -> usize {
+    let mut value = 0;
+    let mut shift = 0;
+    while let Some(&byte) = data.get(*position) {
+        *position += 1;
+        value |= ((byte & 0x7F) as usize) << shift;
+        if byte & 0x80 == 0 {
+            break;
+        }
+        shift += 7;
+    }
+    value
+}
+
+fn encode_delta(current: &[u8], previous: &[u8]) -> Vec<u8> {
+    if current.len() != previous.len() {
+        let mut delta = Vec::with_capacity(previous.len() + 1);
+        delta.push(DELTA_FULL);
+        delta.extend_from_slice(previous);
+        return delta;
+    }
+
+    let mut delta = vec![DELTA_XOR];
+    let mut position = 0;
+    while position < current.len() {
+        let zero_start = position;
+        while position < current.len() && current[position] == previous[position] {
+            position += 1;
+        }
+        let literal_start = position;
+        while position < current.len() && current[position] != previous[position] {
+            position += 1;
+        }
+
+        write_varint(&mut delta, literal_start - zero_start);
+        write_varint(&mut delta, position - literal_start);
+        delta.extend(
+            current[literal_start..position]
+                .iter()
+                .zip(&previous[literal_start..position])
+                .map(|(a, b)| a ^ b),
+        );
+    }
+    delta
+}
+
+fn apply_delta(current: &[u8], delta: &[u8]) -> Vec<u8> {
+    if delta.first() == Some(&DELTA_FULL) {
+        return delta[1..].to_vec();
+    }
+
+    let mut previous = current.to_vec();
+    let mut position = 1;
+    let mut offset = 0;
+    while position < delta.len() {
+        offset += read_varint(delta, &mut position);
+        let literal_len = read_varint(delta, &mut position);
+        for (byte, change) in previous[offset..offset + literal_len]
+            .iter_mut()
+            .zip(&delta[position..position + literal_len])
+        {
+            *byte ^= change;
+        }
+        position += literal_len;
+        offset += literal_len;
+    }
+    previous
+}
@@ -0,0 +1,223 @@
+use std::error::Error;
+use std::fmt;
+
+use crate::chip8::{
+    AUDIO_PATTERN_SIZE, Chip8, KEYPAD_SIZE, MEMORY_SIZE, NUM_REGISTERS, RPL_FLAGS_SIZE, STACK_SIZE, XO_MEMORY_SIZE,
+};
+use crate::quirks::Quirks;
+
+pub const SAVE_STATE_MAGIC: &[u8; 4] = b"C8SS";
+pub const SAVE_STATE_VERSION: u16 = 1;
+pub const EMULATOR_VERSION: &str = env!("CARGO_PKG_VERSION");
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum SaveStateError {
+    BadMagic,
+    UnsupportedFormat { found: u16 },
+    EmulatorVersionMismatch { found: String },
+    QuirksMismatch { found: Quirks, expected: Quirks },
+    MemorySizeMismatch { found: usize, expected: usize },
+    Truncated,
+    Corrupt(&'static str),
+}
+
+impl fmt::Display for SaveStateError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            SaveStateError::BadMagic => write!(f, "not a CHIP-8 save state"),
+            SaveStateError::UnsupportedFormat { found } => write!(
+                f,
+                "save state format version {found} is not supported (expected {SAVE_STATE_VERSION})"
+            ),
+            SaveStateError::EmulatorVersionMismatch { found } => write!(
+                f,
+                "save state was made by emulator version {found}, this is version {EMULATOR_VERSION}"
+            ),
+            SaveStateError::QuirksMismatch { found, expected } => write!(
+                f,
+                "save state uses the {} quirks profile but {} is selected",
+                found.describe(),
+                expected.describe()
+            ),
+            SaveStateError::MemorySizeMismatch { found, expected } => write!(
+                f,
+                "save state has {} KiB of memory but the selected mode has {} KiB",
+                found / 1024,
+                expected / 1024
+            ),
+            SaveStateError::Truncated => write!(f, "save state is truncated"),
+            SaveStateError::Corrupt(what) => write!(f, "save state is corrupt: {what}"),
+        }
+    }
+}
+
+impl Error for SaveStateError {}
+
+struct Reader<'a> {
+    data: &'a [u8],
+}
+
+impl<'a> Reader<'a> {
+    fn take(&mut self, len: usize) -> Result<&'a [u8], SaveStateError> {
+        if self.data.len() < len {
+            return Err(SaveStateError::Truncated);
+        }
+        let (head, tail) = self.data.split_at(len);
+        self.data = tail;
+        Ok(head)
+    }
+
+    fn u8(&mut self) -> Result<u8, SaveStateError> {
+        Ok(self.take(1)?[0])
+    }
+
+    fn u16(&mut self) -> Result<u16, SaveStateError> {
+        Ok(u16::from_le_bytes(self.take(2)?.try_into().unwrap()))
+    }
+
+    fn u32(&mut self) -> Result<u32, SaveStateError> {
+        Ok(u32::from_le_bytes(self.take(4)?.try_into().unwrap()))
+    }
+
+    fn bool(&mut self) -> Result<bool, SaveStateError> {
+        Ok(self.u8()? != 0)
+    }
+
+    fn array<const N: usize>(&mut self) -> Result<[u8; N], SaveStateError> {
+        Ok(self.take(N)?.try_into().unwrap())
+    }
+
+    fn bytes(&mut self) -> Result<Vec<u8>, SaveStateError> {
+        let len = self.u32()? as usize;
+        Ok(self.take(len)?.to_vec())
+    }
+}
+
+struct Header {
+    emulator_version: String,
+    quirks: Quirks,
+}
+
+fn read_header(reader: &mut Reader) -> Result<Header, SaveStateError> {
+    if reader.take(SAVE_STATE_MAGIC.len()).ok() != Some(SAVE_STATE_MAGIC.as_slice()) {
+        return Err(SaveStateError::BadMagic);
+    }
+
+    let format = reader.u16()?;
+    if format != SAVE_STATE_VERSION {
+        return Err(SaveStateError::UnsupportedFormat { found: format });
+    }
+
+    let version_len = reader.u8()? as usize;
+    let emulator_version = String::from_utf8(reader.take(version_len)?.to_vec())
+        .map_err(|_| SaveStateError::Corrupt("emulator version is not UTF-8"))?;
+    let quirks = Quirks::from_bits(reader.u8()?);
+
+    Ok(Header { emulator_version, quirks })
+}
+
+impl Chip8 {
+    pub fn save_state(&self) -> Vec<u8> {
+        let mut out = Vec::with_capacity(self.memory.len() + self.display.len() + 128);
+
+        out.extend_from_slice(SAVE_STATE_MAGIC);
+        out.extend_from_slice(&SAVE_STATE_VERSION.to_le_bytes());
+        out.push(EMULATOR_VERSION.len() as u8);
+        out.extend_from_slice(EMULATOR_VERSION.as_bytes());
+        out.push(self.quirks.to_bits());
+
+        out.extend_from_slice(&self.pc.to_le_bytes());
+        out.extend_from_slice(&self.i.to_le_bytes());
+        out.extend_from_slice(&self.v);
+        for address in self.stack {
+            out.extend_from_slice(&address.to_le_bytes());
+        }
+        out.push(self.sp);
+        out.push(self.delay_timer);
+        out.push(self.sound_timer);
+        out.push(self.audio_pattern.is_some() as u8);
+        out.extend_from_slice(&self.audio_pattern.unwrap_or_default());
+        out.push(self.pitch);
+        out.push(self.planes);
+        out.push(self.hires as u8);
+        out.push(self.halted as u8);
+        out.push(self.vblank as u8);
+        out.extend_from_slice(&self.rpl_flags);
+        out.extend_from_slice(&self.keypad);
+
+        out.extend_from_slice(&(self.memory.len() as u32).to_le_bytes());
+        out.extend_from_slice(&self.memory);
+        out.extend_from_slice(&(self.display.len() as u32).to_le_bytes());
+        out.extend_from_slice(&self.display);
+
+        out
+    }
+
+    pub fn from_state(data: &[u8]) -> Result<Chip8, SaveStateError> {
+        let mut reader = Reader { data };
+        let header = read_header(&mut reader)?;
+
+        let mut chip8 = Chip8::new();
+        chip8.quirks = header.quirks;
+        chip8.pc = reader.u16()?;
+        chip8.i = reader.u16()?;
+        chip8.v = reader.array::<NUM_REGISTERS>()?;
+        for address in chip8.stack.iter_mut() {
+            *address = reader.u16()?;
+        }
+        chip8.sp = reader.u8()?;
+        chip8.delay_timer = reader.u8()?;
+        chip8.sound_timer = reader.u8()?;
+        let has_pattern = reader.bool()?;
+        let pattern = reader.array::<AUDIO_PATTERN_SIZE>()?;
+        chip8.audio_pattern = has_pattern.then_some(pattern);
+        chip8.pitch = reader.u8()?;
+        chip8.planes = reader.u8()?;
+        chip8.hires = reader.bool()?;
+        chip8.halted = reader.bool()?;
+        chip8.vblank = reader.bool()?;
+        chip8.rpl_flags = reader.array::<RPL_FLAGS_SIZE>()?;
+        chip8.keypad = reader.array::<KEYPAD_SIZE>()?;
+        chip8.memory = reader.bytes()?;
+        chip8.display = reader.bytes()?;
+
+        if chip8.memory.len() != MEMORY_SIZE && chip8.memory.len() != XO_MEMORY_SIZE {
+            return Err(SaveStateError::Corrupt("memory size is neither 4 KiB nor 64 KiB"));
+        }
+        if chip8.pc as usize >= chip8.memory.len() {
+            return Err(SaveStateError::Corrupt("program counter outside memory"));
+        }
+        if chip8.sp as usize > STACK_SIZE {
+            return Err(SaveStateError::Corrupt("stack pointer out of range"));
+        }
+        if chip8.display.len() != chip8.display_width() * chip8.display_height() {
+            return Err(SaveStateError::Corrupt("display size does not match resolution"));
+        }
+        if !reader.data.is_empty() {
+            return Err(SaveStateError::Corrupt("trailing data"));
+        }
+
+        Ok(chip8)
+    }
+
+    pub fn load_state(&mut self, data: &[u8]) -> Result<(), SaveStateError> {
+        let header = read_header(&mut Reader { data })?;
+        if header.emulator_version != EMULATOR_VERSION {
+            return Err(SaveStateError::EmulatorVersionMismatch { found: header.emulator_version });
+        }
+        if header.quirks != self.quirks {
+            return Err(SaveStateError::QuirksMismatch { found: header.quirks, expected: self.quirks });
+        }
+
+        let state = Chip8::from_state(data)?;
+        if state.memory.len() != self.memory.len() {
+            return Err(SaveStateError::MemorySizeMismatch { found: state.memory.len(), expected: self.memory.len() });
+        }
+        let coverage = self.coverage.take();
+        let journal_capacity = self.journal.capacity();
+        *self = state;
+        self.coverage = coverage;
+        self.journal.set_capacity(journal_capacity);
+        Ok(())
+    }
+}
@@ -0,0 +1,293 @@
+use std::collections::{BTreeMap, HashMap};
+use std::error::Error;
+use std::fmt;
+
+use crate::disasm;
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct SymbolError {
+    pub line: usize,
+    pub message: String,
+}
+
+impl fmt::Display for SymbolError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "line {}: {}", self.line, self.message)
+    }
+}
+
+impl Error for SymbolError {}
+
+#[derive(Debug, Clone, Default)]
+pub struct SymbolTable {
+    by_address: BTreeMap<u16, String>,
+    by_name: HashMap<String, u16>,
+}
+
+fn parse_hex(text: &str) -> Option<u16> {
+    let digits = text.strip_prefix("0x").or_else(|| text.strip_prefix("0X")).unwrap_or(text);
+    u16::from_str_radix(digits, 16).ok()
+}
+
+// Plain symbol files need a prefix so that `512 main` is not silently read as 0x512.
+fn parse_prefixed_hex(text: &str) -> Option<u16> {
+    let digits = text.strip_prefix("0x").or_else(|| text.strip_prefix("0X")).or_else(|| text.strip_prefix('$'))?;
+    u16::from_str_radix(digits, 16).ok()
+}
+
+impl SymbolTable {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn parse(text: &str) -> Result<Self, SymbolError> {
+        if text.trim_start().starts_with('{') {
+            Self::parse_octo(text)
+        } else {
+            Self::parse_plain(text)
+        }
+    }
+
+    fn parse_plain(text: &str) -> Result<Self, SymbolError> {
+        let mut table = Self::new();
+        for (index, line) in text.lines().enumerate() {
+            let line = line.split(['#', ';']).next().unwrap_or("").trim();
+            if line.is_empty() {
+                continue;
+            }
+            let error = |message: String| SymbolError { line: index + 1, message };
+            let mut fields = line.split_whitespace();
+            let (Some(address), Some(name), None) = (fields.next(), fields.next(), fields.next()) else {
+                return Err(error("expected `address name`".to_string()));
+            };
+            let address = parse_prefixed_hex(address)
+                .ok_or_else(|| error(format!("invalid address '{address}' (use 0x or $ for hex)")))?;
+            table.insert(address, name);
+        }
+        Ok(table)
+    }
+
+    fn parse_octo(text: &str) -> Result<Self, SymbolError> {
+        let mut scanner = JsonScanner { text, pos: 0 };
+        let mut table = Self::new();
+        scanner.expect('{')?;
+        if scanner.eat('}') {
+            return Ok(table);
+        }
+        loop {
+            let key = scanner.string()?;
+            scanner.expect(':')?;
+            if key == "labels" {
+                scanner.expect('{')?;
+                if !scanner.eat('}') {
+                    loop {
+                        let name = scanner.string()?;
+                        scanner.expect(':')?;
+                        let line = scanner.line();
+                        let address = scanner.number()?;
+                        let address = u16::try_from(address).map_err(|_| SymbolError {
+                            line,
+                            message: format!("address {address} of '{name}' is out of range"),
+                        })?;
+                        table.insert(address, &name);
+                        if !scanner.eat(',') {
+                            break;
+                        }
+                    }
+                    scanner.expect('}')?;
+                }
+            } else {
+                scanner.skip_value()?;
+            }
+            if !scanner.eat(',') {
+                break;
+            }
+        }
+        scanner.expect('}')?;
+        Ok(table)
+    }
+
+    pub fn insert(&mut self, address: u16, name: &str) {
+        if let Some(old) = self.by_name.insert(name.to_string(), address)
+            && old != address
+            && self.by_address.get(&old).is_some_and(|shown| shown == name)
+        {
+            self.by_address.remove(&old);
+            let alias = self.by_name.iter().filter(|&(_, &other)| other == old).map(|(alias, _)| alias).min();
+            if let Some(alias) = alias.cloned() {
+                self.by_address.insert(old, alias);
+            }
+        }
+        self.by_address.entry(address).or_insert_with(|| name.to_string());
+    }
+
+    /// Adds the symbols of `other`, whose names replace ours where both name an address.
+    pub fn extend(&mut self, other: SymbolTable) {
+        let mut names: Vec<(String, u16)> = other.by_name.into_iter().collect();
+        names.sort();
+        for (name, address) in names {
+            self.insert(address, &name);
+        }
+        self.by_address.extend(other.by_address);
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.by_name.is_empty()
+    }
+
+    pub fn len(&self) -> usize {
+        self.by_name.len()
+    }
+
+    pub fn name(&self, address: u16) -> Option<&str> {
+        self.by_address.get(&address).map(String::as_str)
+    }
+
+    pub fn describe(&self, address: u16) -> Option<String> {
+        let (base, name) = self.by_address.range(..=address).next_back()?;
+        match address - base {
+            0 => Some(name.clone()),
+            offset => Some(format!("{}+{:#X}", name, offset)),
+        }
+    }
+
+    pub fn format_address(&self, address: u16) -> String {
+        self.describe(address).unwrap_or_else(|| format!("{:#06X}", address))
+    }
+
+    pub fn resolve(&self, text: &str) -> Option<u16> {
+        let text = text.trim();
+        let (name, offset) = match text.split_once('+') {
+            Some((name, offset)) => (name.trim(), parse_hex(offset.trim())?),
+            None => (text, 0),
+        };
+        self.by_name.get(name)?.checked_add(offset)
+    }
+
+    pub fn disassemble(&self, opcode: u16) -> String {
+        let nnn = opcode & 0x0FFF;
+        let Some(target) = self.describe(nnn) else {
+            return disasm::disassemble(opcode);
+        };
+        match opcode >> 12 {
+            0x1 => format!("JP   {}", target),
+            0x2 => format!("CALL {}", target),
+            0xA => format!("LD   I, {}", target),
+            0xB => format!("JP   V0, {}", target),
+            _ => disasm::disassemble(opcode),
+        }
+    }
+}
+
+impl From<BTreeMap<String, u16>> for SymbolTable {
+    fn from(labels: BTreeMap<String, u16>) -> Self {
+        let mut table = Self::new();
+        for (name, address) in labels {
+            table.insert(address, &name);
+        }
+        table
+    }
+}
+
+struct JsonScanner<'a> {
+    text: &'a str,
+    pos: usize,
+}
+
+impl JsonScanner<'_> {
+    fn line(&self) -> usize {
+        self.text[..self.pos].matches('\n').count() + 1
+    }
+
+    fn error(&self, message: String) -> SymbolError {
+        SymbolError { line: self.line(), message }
+    }
+
+    fn peek(&mut self) -> Option<char> {
+        let rest = &self.text[self.pos..];
+        self.pos += rest.len() - rest.trim_start().len();
+        self.text[self.pos..].chars().next()
+    }
+
+    fn eat(&mut self, expected: char) -> bool {
+        if self.peek() == Some(expected) {
+            self.pos += expected.len_utf8();
+            true
+        } else {
+            false
+        }
+    }
+
+    fn expect(&mut self, expected: char) -> Result<(), SymbolError> {
+        if self.eat(expected) {
+            Ok(())
+        } else {
+            Err(self.error(format!("expected '{expected}'")))
+        }
+    }
+
+    fn string(&mut self) -> Result<String, SymbolError> {
+        self.expect('"')?;
+        let mut value = String::new();
+        let mut chars = self.text[self.pos..].char_indices();
+        while let Some((offset, c)) = chars.next() {
+            match c {
+                '"' => {
+                    self.pos += offset + 1;
+                    return Ok(value);
+                }
+                '\\' => match chars.next() {
+                    Some((_, escaped)) => value.push(escaped),
+                    None => break,
+                },
+                _ => value.push(c),
+            }
+        }
+        Err(self.error("unterminated string".to_string()))
+    }
+
+    fn number(&mut self) -> Result<i64, SymbolError> {
+        self.peek();
+        let rest = &self.text[self.pos..];
+        let len = rest
+            .find(|c: char| !(c.is_ascii_digit() || matches!(c, '-' | '+' | '.' | 'e' | 'E')))
+            .unwrap_or(rest.len());
+        let value = rest[..len].parse::<i64>().map_err(|_| self.error("expected an integer address".to_string()))?;
+        self.pos += len;
+        Ok(value)
+    }
+
+    fn skip_value(&mut self) -> Result<(), SymbolError> {
+        match self.peek() {
+            Some('"') => self.string().map(drop),
+            Some(open @ ('{' | '[')) => {
+                let close = if open == '{' { '}' } else { ']' };
+                self.pos += 1;
+                if self.eat(close) {
+                    return Ok(());
+                }
+                loop {
+                    if open == '{' {
+                        self.string()?;
+                        self.expect(':')?;
+                    }
+                    self.skip_value()?;
+                    if !self.eat(',') {
+                        break;
+                    }
+                }
+                self.expect(close)
+            }
+            Some(_) => {
+                let rest = &self.text[self.pos..];
+                let len = rest.find([',', '}', ']']).unwrap_or(rest.len());
+                if rest[..len].trim().is_empty() {
+                    return Err(self.error("expected a value".to_string()));
+                }
+                self.pos += len;
+                Ok(())
+            }
+            None => Err(self.error("unexpected end of file".to_string())),
+        }
+    }
+}
@@ -0,0 +1,162 @@
+use std::collections::VecDeque;
+use std::fmt;
+use std::io::{self, Write};
+use std::ops::RangeInclusive;
+
+use crate::chip8::{Chip8, NUM_REGISTERS};
+use crate::disasm;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct TraceEntry {
+    pub cycle: u64,
+    pub pc: u16,
+    pub opcode: Option<u16>,
+    pub v: [u8; NUM_REGISTERS],
+    pub i: u16,
+    pub sp: u8,
+    pub delay_timer: u8,
+    pub sound_timer: u8,
+}
+
+impl TraceEntry {
+    pub fn capture(cycle: u64, chip8: &Chip8) -> Self {
+        let pc = chip8.pc as usize;
+        let opcode = match chip8.memory.get(pc..pc + 2) {
+            Some(&[hi, lo]) => Some(((hi as u16) << 8) | lo as u16),
+            _ => None,
+        };
+        TraceEntry {
+            cycle,
+            pc: chip8.pc,
+            opcode,
+            v: chip8.v,
+            i: chip8.i,
+            sp: chip8.sp,
+            delay_timer: chip8.delay_timer,
+            sound_timer: chip8.sound_timer,
+        }
+    }
+}
+
+impl fmt::Display for TraceEntry {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let v: Vec<String> = self.v.iter().map(|value| format!("{:02X}", value)).collect();
+        let (opcode, text) = match self.opcode {
+            Some(opcode) => (format!("{:04X}", opcode), disasm::disassemble(opcode)),
+            None => ("????".to_string(), "???".to_string()),
+        };
+        write!(
+            f,
+            "{:>8} PC={:04X} OP={} V={} I={:04X} SP={:02X} DT={:02X} ST={:02X} ; {}",
+            self.cycle,
+            self.pc,
+            opcode,
+            v.join(","),
+            self.i,
+            self.sp,
+            self.delay_timer,
+            self.sound_timer,
+            text
+        )
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum TraceMode {
+    Stream,
+    Ring(usize),
+}
+
+pub struct Tracer {
+    writer: Box<dyn Write>,
+    mode: TraceMode,
+    ring: VecDeque<TraceEntry>,
+    ranges: Vec<RangeInclusive<u16>>,
+    cycle: u64,
+    error: Option<io::Error>,
+}
+
+pub fn parse_ranges(text: &str) -> Option<Vec<RangeInclusive<u16>>> {
+    let parse = |text: &str| {
+        let text = text.trim();
+        let digits = text.strip_prefix("0x").or_else(|| text.strip_prefix("0X")).unwrap_or(text);
+        u16::from_str_radix(digits, 16).ok()
+    };
+    text.split(',')
+        .map(str::trim)
+        .filter(|range| !range.is_empty())
+        .map(|range| {
+            let (start, end) = range.split_once('-').unwrap_or((range, range));
+            let (start, end) = (parse(start)?, parse(end)?);
+            (start <= end).then_some(start..=end)
+        })
+        .collect()
+}
+
+impl Tracer {
+    pub fn new(writer: Box<dyn Write>, mode: TraceMode) -> Self {
+        Tracer {
+            writer,
+            mode,
+            ring: VecDeque::new(),
+            ranges: Vec::new(),
+            cycle: 0,
+            error: None,
+        }
+    }
+
+    pub fn mode(&self) -> TraceMode {
+        self.mode
+    }
+
+    pub fn set_ranges(&mut self, ranges: Vec<RangeInclusive<u16>>) {
+        self.ranges = ranges;
+    }
+
+    pub fn record(&mut self, chip8: &Chip8) {
+        if chip8.halted {
+            return;
+        }
+        let cycle = self.cycle;
+        self.cycle += 1;
+        // A DXYN stalled by the display_wait quirk is retried every cycle until
+        // the next frame; only the attempt that draws is logged.
+        if chip8.waiting_for_vblank() {
+            return;
+        }
+        if !self.ranges.is_empty() && !self.ranges.iter().any(|range| range.contains(&chip8.pc)) {
+            return;
+        }
+
+        let entry = TraceEntry::capture(cycle, chip8);
+        match self.mode {
+            TraceMode::Stream => {
+                if self.error.is_none()
+                    && let Err(err) = writeln!(self.writer, "{}", entry)
+                {
+                    self.error = Some(err);
+                }
+            }
+            TraceMode::Ring(capacity) => {
+                if self.ring.len() >= capacity.max(1) {
+                    self.ring.pop_front();
+                }
+                self.ring.push_back(entry);
+            }
+        }
+    }
+
+    pub fn dump(&mut self) -> io::Result<()> {
+        if let Some(err) = self.error.take() {
+            return Err(err);
+        }
+        for entry in self.ring.drain(..) {
+            writeln!(self.writer, "{}", entry)?;
+        }
+        self.writer.flush()
+    }
+
+    pub fn finish(mut self) -> io::Result<()> {
+        self.dump()
+    }
+}
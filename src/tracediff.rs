@@ -0,0 +1,223 @@
+use std::collections::VecDeque;
+use std::fmt;
+
+use crate::chip8::{Chip8, NUM_REGISTERS};
+use crate::clock::Clock;
+use crate::error::Chip8Error;
+use crate::trace::TraceEntry;
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct TraceDiffError {
+    pub line: usize,
+    pub message: String,
+}
+
+impl fmt::Display for TraceDiffError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "reference line {}: {}", self.line, self.message)
+    }
+}
+
+impl std::error::Error for TraceDiffError {}
+
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct ReferenceState {
+    pub line: usize,
+    pub text: String,
+    pub pc: Option<u16>,
+    pub opcode: Option<u16>,
+    pub v: [Option<u8>; NUM_REGISTERS],
+    pub i: Option<u16>,
+    pub sp: Option<u8>,
+    pub delay_timer: Option<u8>,
+    pub sound_timer: Option<u8>,
+    pub memory: Vec<(u16, Vec<u8>)>,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Mismatch {
+    pub field: String,
+    pub expected: String,
+    pub actual: String,
+}
+
+pub struct Divergence {
+    pub reference: ReferenceState,
+    pub actual: TraceEntry,
+    pub mismatches: Vec<Mismatch>,
+    pub fault: Option<Chip8Error>,
+    pub history: Vec<TraceEntry>,
+}
+
+pub enum DiffOutcome {
+    Matched { instructions: usize },
+    Diverged(Box<Divergence>),
+}
+
+fn parse_hex<T: TryFrom<u32>>(text: &str) -> Option<T> {
+    let digits = text.strip_prefix("0x").or_else(|| text.strip_prefix("0X")).unwrap_or(text);
+    u32::from_str_radix(digits, 16).ok().and_then(|value| T::try_from(value).ok())
+}
+
+impl ReferenceState {
+    pub fn parse(line: usize, text: &str) -> Result<Option<Self>, TraceDiffError> {
+        let fields = text.split(';').next().unwrap_or("").trim();
+        if fields.is_empty() || fields.starts_with('#') {
+            return Ok(None);
+        }
+
+        let error = |message: String| TraceDiffError { line, message };
+        let mut state = ReferenceState { line, text: text.trim().to_string(), ..Default::default() };
+        for (index, token) in fields.split_whitespace().enumerate() {
+            let Some((key, value)) = token.split_once('=') else {
+                if index == 0 && token.bytes().all(|byte| byte.is_ascii_digit()) {
+                    continue;
+                }
+                return Err(error(format!("expected `KEY=value`, found '{token}'")));
+            };
+            let invalid = || error(format!("invalid value '{value}' for {key}"));
+            let key = key.to_ascii_uppercase();
+            match key.as_str() {
+                "PC" => state.pc = Some(parse_hex(value).ok_or_else(invalid)?),
+                "OP" => state.opcode = Some(parse_hex(value).ok_or_else(invalid)?),
+                "I" => state.i = Some(parse_hex(value).ok_or_else(invalid)?),
+                "SP" => state.sp = Some(parse_hex(value).ok_or_else(invalid)?),
+                "DT" => state.delay_timer = Some(parse_hex(value).ok_or_else(invalid)?),
+                "ST" => state.sound_timer = Some(parse_hex(value).ok_or_else(invalid)?),
+                "V" => {
+                    let values: Vec<&str> = value.split(',').collect();
+                    if values.len() != NUM_REGISTERS {
+                        return Err(error(format!("V needs {NUM_REGISTERS} comma-separated values")));
+                    }
+                    for (register, value) in values.into_iter().enumerate() {
+                        state.v[register] = Some(parse_hex(value).ok_or_else(invalid)?);
+                    }
+                }
+                _ if key.len() == 2 && key.starts_with('V') => {
+                    let register = parse_hex::<usize>(&key[1..]).ok_or_else(|| error(format!("unknown field '{key}'")))?;
+                    state.v[register] = Some(parse_hex(value).ok_or_else(invalid)?);
+                }
+                _ if key.starts_with("M[") && key.ends_with(']') => {
+                    let address = parse_hex(&key[2..key.len() - 1]).ok_or_else(|| error(format!("invalid address in {key}")))?;
+                    if value.is_empty() || value.len() % 2 != 0 {
+                        return Err(invalid());
+                    }
+                    let bytes = (0..value.len())
+                        .step_by(2)
+                        .map(|offset| value.get(offset..offset + 2).and_then(parse_hex))
+                        .collect::<Option<Vec<u8>>>()
+                        .ok_or_else(invalid)?;
+                    state.memory.push((address, bytes));
+                }
+                _ => return Err(error(format!("unknown field '{key}'"))),
+            }
+        }
+        Ok(Some(state))
+    }
+
+    pub fn parse_log(text: &str) -> Result<Vec<Self>, TraceDiffError> {
+        let mut states = Vec::new();
+        for (index, line) in text.lines().enumerate() {
+            if let Some(state) = Self::parse(index + 1, line)? {
+                states.push(state);
+            }
+        }
+        Ok(states)
+    }
+
+    pub fn compare(&self, chip8: &Chip8) -> Vec<Mismatch> {
+        let entry = TraceEntry::capture(0, chip8);
+        let mut checks = vec![
+            ("PC".to_string(), self.pc.map(u32::from), Some(entry.pc as u32), 4),
+            ("OP".to_string(), self.opcode.map(u32::from), entry.opcode.map(u32::from), 4),
+        ];
+        for (register, expected) in self.v.iter().enumerate() {
+            checks.push((format!("V{:X}", register), expected.map(u32::from), Some(entry.v[register] as u32), 2));
+        }
+        checks.push(("I".to_string(), self.i.map(u32::from), Some(entry.i as u32), 4));
+        checks.push(("SP".to_string(), self.sp.map(u32::from), Some(entry.sp as u32), 2));
+        checks.push(("DT".to_string(), self.delay_timer.map(u32::from), Some(entry.delay_timer as u32), 2));
+        checks.push(("ST".to_string(), self.sound_timer.map(u32::from), Some(entry.sound_timer as u32), 2));
+        for (start, bytes) in &self.memory {
+            for (offset, &expected) in bytes.iter().enumerate() {
+                let address = *start as usize + offset;
+                let actual = chip8.memory.get(address).map(|&byte| byte as u32);
+                checks.push((format!("M[{:04X}]", address), Some(expected as u32), actual, 2));
+            }
+        }
+
+        checks
+            .into_iter()
+            .filter_map(|(field, expected, actual, digits)| {
+                let expected = expected?;
+                if actual == Some(expected) {
+                    return None;
+                }
+                let hex = |value: u32| format!("{:#0width$X}", value, width = digits + 2);
+                Some(Mismatch {
+                    field,
+                    expected: hex(expected),
+                    actual: actual.map_or_else(|| "out of memory".to_string(), hex),
+                })
+            })
+            .collect()
+    }
+}
+
+pub fn diff_trace(chip8: &mut Chip8, clock: &mut Clock, reference: Vec<ReferenceState>, context: usize) -> DiffOutcome {
+    assert!(clock.instructions_per_second > 0, "diff_trace needs a clock that runs instructions");
+    let mut history = VecDeque::with_capacity(context + 1);
+    let mut remaining = clock.virtual_frame();
+    let instructions = reference.len();
+    let mut reference = reference.into_iter().enumerate().peekable();
+
+    while let Some((cycle, state)) = reference.next() {
+        // Traces only log the attempt of a DXYN that draws, so cycles stalled
+        // by display_wait pass without consuming a reference line.
+        loop {
+            while remaining == 0 {
+                chip8.tick_timers();
+                remaining = clock.virtual_frame();
+            }
+            if !chip8.waiting_for_vblank() {
+                break;
+            }
+            remaining -= 1;
+        }
+
+        let actual = TraceEntry::capture(cycle as u64, chip8);
+        let mismatches = state.compare(chip8);
+        let fault = if mismatches.is_empty() { chip8.emulate_cycle().err() } else { None };
+        if !mismatches.is_empty() || fault.is_some() {
+            return DiffOutcome::Diverged(Box::new(Divergence {
+                reference: state,
+                actual,
+                mismatches,
+                fault,
+                history: history.into(),
+            }));
+        }
+
+        // The random byte of CXNN cannot match the reference's generator, so
+        // VX takes the value the reference shows on its next line instead.
+        if let Some(opcode) = actual.opcode
+            && opcode >> 12 == 0xC
+            && let Some((_, next)) = reference.peek()
+        {
+            let x = (opcode as usize >> 8) & 0xF;
+            if let Some(value) = next.v[x] {
+                chip8.v[x] = value;
+            }
+        }
+
+        if history.len() == context {
+            history.pop_front();
+        }
+        if context > 0 {
+            history.push_back(actual);
+        }
+        remaining -= 1;
+    }
+
+    DiffOutcome::Matched { instructions }
+}
@@ -1,7 +1,11 @@
-use crate::chip8::{self, Chip8};
-use crate::disasm;
 use crate::MyApp;
-use crate::app::ExecutionState; 
+use crate::app::{DebuggerState, ExecutionState, MemoryViewState, RECENT_WRITE_FRAMES, RegisterField, WatchType};
+use chip8_emulator::breakpoints::{WatchAccess, Watchpoint};
+use chip8_emulator::chip8::{self, Chip8};
+use chip8_emulator::listing::Gutter;
+use chip8_emulator::profiler;
+use chip8_emulator::quirks::QuirksPreset;
+use chip8_emulator::symbols::SymbolTable;
 use eframe::egui;
 use rfd::FileDialog;
 
@@ -12,18 +16,152 @@ pub fn draw_menu_bar(app: &mut MyApp, ctx: &egui::Context) {
                 if ui.button("Open ROM...").clicked() {
                     if let Some(path) = FileDialog::new()
                         .add_filter("Chip-8 ROM", &["ch8", ""])
+                        .add_filter("Octo source", &["8o"])
                         .pick_file()
                     {
                         app.rom_to_load = Some(path.display().to_string());
                     }
                     ui.close();
                 }
+                if ui.add_enabled(app.coverage_enabled, egui::Button::new("Export Coverage...")).clicked() {
+                    if let Some(path) = FileDialog::new()
+                        .add_filter("Coverage listing", &["lst"])
+                        .save_file()
+                    {
+                        app.export_coverage(&path);
+                    }
+                    ui.close();
+                }
+                if ui.button("Load Symbols...").clicked() {
+                    if let Some(path) = FileDialog::new()
+                        .add_filter("Symbol map", &["sym", "txt", "json"])
+                        .pick_file()
+                    {
+                        app.load_symbols(&path);
+                    }
+                    ui.close();
+                }
+
+                if app.is_recording_audio() {
+                    if ui.button("Stop Audio Recording").clicked() {
+                        app.stop_audio_recording();
+                        ui.close();
+                    }
+                } else if ui.button("Record Audio...").clicked() {
+                    if let Some(path) = FileDialog::new()
+                        .add_filter("WAV audio", &["wav"])
+                        .save_file()
+                    {
+                        app.start_audio_recording(&path);
+                    }
+                    ui.close();
+                }
+            });
+
+            ui.menu_button("State", |ui| {
+                if ui.button("Quick Save (F5)").clicked() {
+                    app.quick_save();
+                    ui.close();
+                }
+                if ui.button("Quick Load (F9)").clicked() {
+                    app.quick_load();
+                    ui.close();
+                }
+                ui.separator();
+                for slot in 1..=4 {
+                    ui.radio_value(&mut app.save_slot, slot, format!("Slot {slot}"));
+                }
             });
 
             ui.menu_button("Options", |ui| {
                 if ui.toggle_value(&mut app.debugger_open, "Show Debugger").clicked() {
                     ui.close(); 
                 }
+                if ui.toggle_value(&mut app.memory_view.open, "Show Memory").clicked() {
+                    ui.close();
+                }
+                if ui.toggle_value(&mut app.profiler_view.open, "Show Profiler").clicked() {
+                    ui.close();
+                }
+                let mut coverage = app.coverage_enabled;
+                if ui.checkbox(&mut coverage, "Track Coverage").changed() {
+                    app.set_coverage(coverage);
+                }
+
+                ui.horizontal(|ui| {
+                    ui.label("Speed");
+                    ui.add(
+                        egui::DragValue::new(&mut app.clock.instructions_per_second)
+                            .range(1..=100_000)
+                            .suffix(" IPS"),
+                    );
+                });
+
+                ui.horizontal(|ui| {
+                    const MIB: usize = 1024 * 1024;
+                    let mut budget = app.rewind.budget() / MIB;
+                    ui.label("Rewind buffer");
+                    if ui.add(egui::DragValue::new(&mut budget).range(1..=1024).suffix(" MiB")).changed() {
+                        app.rewind.set_budget(budget * MIB);
+                    }
+                    ui.weak(format!("{:.1} MiB used", app.rewind.memory_used() as f32 / MIB as f32));
+                });
+
+                ui.menu_button("Sound", |ui| {
+                    let settings = &mut app.audio_generator.settings;
+                    ui.checkbox(&mut settings.muted, "Mute");
+                    ui.add(egui::Slider::new(&mut settings.volume, 0.0..=1.0).text("Volume"));
+                    ui.add(
+                        egui::Slider::new(&mut settings.frequency, 100.0..=2000.0)
+                            .text("Frequency")
+                            .suffix(" Hz"),
+                    );
+                });
+
+                ui.menu_button("Trace", |ui| {
+                    let tracing = app.is_tracing();
+                    ui.add_enabled_ui(!tracing, |ui| {
+                        ui.horizontal(|ui| {
+                            ui.checkbox(&mut app.trace_settings.ring, "Only keep the last");
+                            ui.add_enabled(
+                                app.trace_settings.ring,
+                                egui::DragValue::new(&mut app.trace_settings.ring_size)
+                                    .range(1..=1_000_000)
+                                    .suffix(" instructions"),
+                            );
+                        });
+                        ui.horizontal(|ui| {
+                            ui.label("Addresses");
+                            ui.add(
+                                egui::TextEdit::singleline(&mut app.trace_settings.ranges)
+                                    .hint_text("0x200-0x2FF, 0x340")
+                                    .desired_width(140.0),
+                            );
+                        });
+                    });
+                    if tracing {
+                        if ui.button("Stop Trace").clicked() {
+                            app.stop_trace();
+                            ui.close();
+                        }
+                    } else if ui.button("Start Trace...").clicked() {
+                        if let Some(path) = FileDialog::new()
+                            .add_filter("Trace log", &["log", "txt"])
+                            .save_file()
+                        {
+                            app.start_trace(&path);
+                        }
+                        ui.close();
+                    }
+                });
+
+                ui.menu_button("Quirks", |ui| {
+                    for preset in QuirksPreset::ALL {
+                        if ui.radio_value(&mut app.quirks_preset, preset, preset.name()).clicked() {
+                            ui.close();
+                        }
+                    }
+                });
             });
 
             ui.add_space(10.0);
@@ -39,28 +177,55 @@ pub fn draw_menu_bar(app: &mut MyApp, ctx: &egui::Context) {
                     ExecutionState::Running => ExecutionState::Paused,
                     ExecutionState::Paused => ExecutionState::Running,
                 };
+                app.debugger.last_break = None;
                 ui.close();
             };
 
             let is_paused = app.execution_state == ExecutionState::Paused;
+                let reverse_continue_button = egui::Button::new("⏮");
+                if ui.add_enabled(is_paused && app.can_step_back(), reverse_continue_button)
+                    .on_hover_text("Run backwards to the previous breakpoint")
+                    .clicked()
+                {
+                    app.reverse_continue_requested = true;
+                    ui.close();
+                };
+                let step_back_button = egui::Button::new("⬅");
+                if ui.add_enabled(is_paused && app.can_step_back(), step_back_button).clicked() {
+                    app.step_back_requested = true;
+                    ui.close();
+                };
                 let step_button = egui::Button::new("➡");
                 if ui.add_enabled(is_paused, step_button).clicked() {
                     app.step_requested = true;
                     ui.close();
             };
+
+            let rewind_button = ui.add_enabled(!is_paused, egui::Button::new("⏪"))
+                .on_hover_text("Hold to rewind (Backspace)");
+            app.rewind_held = rewind_button.is_pointer_button_down_on();
         });
     });
 }
 
-pub fn draw_debugger_panel(chip8: &Chip8, ctx: &egui::Context) {
+pub fn draw_debugger_panel(chip8: &mut Chip8, debugger: &mut DebuggerState, editable: bool, ctx: &egui::Context) {
+    if !editable {
+        debugger.editing = None;
+    }
+
     egui::TopBottomPanel::bottom("debugger_panel")
         .default_height(200.0)
         .resizable(true)
         .show(ctx, |ui| {
-            ui.heading("Debugger");
+            ui.horizontal(|ui| {
+                ui.heading("Debugger");
+                if let Some(reason) = &debugger.last_break {
+                    ui.colored_label(egui::Color32::from_rgb(230, 80, 80), format!("Stopped: {}", reason));
+                }
+            });
             ui.add_space(5.0);
 
-            ui.columns(2, |columns| {
+            ui.columns(4, |columns| {
                 columns[0].vertical(|ui| {
                     ui.label(egui::RichText::new("Registers").underline());
                     egui::Grid::new("registers_grid")
@@ -68,64 +233,619 @@ pub fn draw_debugger_panel(chip8: &Chip8, ctx: &egui::Context) {
                         .spacing([20.0, 4.0])
                         .show(ui, |ui| {
                             for i in 0..8 {
-                                ui.label(format!("V{:X}", i));
-                                ui.monospace(format!("{:#04X}", chip8.v[i]));
-                                ui.label(format!("V{:X}", i + 8));
-                                ui.monospace(format!("{:#04X}", chip8.v[i + 8]));
+                                for reg in [i, i + 8] {
+                                    ui.label(format!("V{:X}", reg));
+                                    register_value(ui, chip8, debugger, editable, RegisterField::V(reg));
+                                }
                                 ui.end_row();
                             }
                         });
                     ui.separator();
-                    egui::Grid::new("special_registers_grid").show(ui, |ui| {
-                        ui.label("PC"); ui.monospace(format!("{:#06X}", chip8.pc)); ui.end_row();
-                        ui.label("I"); ui.monospace(format!("{:#06X}", chip8.i)); ui.end_row();
-                        ui.label("SP"); ui.monospace(format!("{:#04X}", chip8.sp)); ui.end_row();
+                    egui::Grid::new("special_registers_grid")
+                        .num_columns(4)
+                        .spacing([20.0, 4.0])
+                        .show(ui, |ui| {
+                            ui.label("PC"); register_value(ui, chip8, debugger, editable, RegisterField::Pc);
+                            ui.label("DT"); register_value(ui, chip8, debugger, editable, RegisterField::DelayTimer);
+                            ui.end_row();
+                            ui.label("I"); register_value(ui, chip8, debugger, editable, RegisterField::I);
+                            ui.label("ST"); register_value(ui, chip8, debugger, editable, RegisterField::SoundTimer);
+                            ui.end_row();
+                            ui.label("SP"); register_value(ui, chip8, debugger, editable, RegisterField::Sp);
+                            ui.end_row();
+                        });
+                    ui.separator();
+                    ui.collapsing("Stack", |ui| {
+                        egui::Grid::new("stack_grid")
+                            .num_columns(4)
+                            .spacing([20.0, 4.0])
+                            .show(ui, |ui| {
+                                for i in 0..chip8::STACK_SIZE / 2 {
+                                    for slot in [i, i + chip8::STACK_SIZE / 2] {
+                                        ui.label(format!("{:X}", slot));
+                                        register_value(ui, chip8, debugger, editable, RegisterField::Stack(slot));
+                                    }
+                                    ui.end_row();
+                                }
+                            });
                     });
+                    if let Some(err) = &debugger.edit_error {
+                        ui.colored_label(egui::Color32::from_rgb(230, 80, 80), err);
+                    }
                 });
 
                 columns[1].vertical(|ui| {
                     ui.label(egui::RichText::new("Disassembly").underline());
+                    debugger.follow_pc(chip8.pc);
                     egui::ScrollArea::vertical().show(ui, |ui| {
                         let current_pc = chip8.pc;
-                        for offset in -6..=7 {
-                            let addr = current_pc.wrapping_add_signed(offset * 2);
-                            if addr as usize > chip8::MEMORY_SIZE - 2 { continue; }
-                            
-                            let hi = chip8.memory[addr as usize] as u16;
-                            let lo = chip8.memory[addr as usize + 1] as u16;
-                            let opcode = (hi << 8) | lo;
-                            let disasm_text = disasm::disassemble(opcode);
-                            let text = format!("{:#06X}: {}", addr, disasm_text);
+                        let pc_row = debugger.listing_rows.iter().position(|(gutter, _)| {
+                            matches!(gutter, Gutter::Code { address, .. } if *address == current_pc)
+                        });
+                        let rows: Vec<(Gutter, String)> = match pc_row {
+                            Some(row) => {
+                                let start = row.saturating_sub(6);
+                                let end = (row + 8).min(debugger.listing_rows.len());
+                                debugger.listing_rows[start..end].to_vec()
+                            }
+                            None => raw_disassembly(chip8, &debugger.symbols),
+                        };
+
+                        for (gutter, text) in rows {
+                            let addr = match gutter {
+                                Gutter::Header => continue,
+                                Gutter::Label { .. } => {
+                                    ui.label(
+                                        egui::RichText::new(format!("  {}", text))
+                                            .monospace()
+                                            .color(egui::Color32::from_rgb(230, 200, 90)),
+                                    );
+                                    continue;
+                                }
+                                Gutter::Data { address, .. } => {
+                                    ui.label(egui::RichText::new(format!("  {:#06X}: {}", address, text)).monospace().weak());
+                                    continue;
+                                }
+                                Gutter::Code { address, .. } => address,
+                            };
+                            let marker = if debugger.breakpoints.contains(addr) { "●" } else { " " };
+                            let text = format!("{} {:#06X}: {}", marker, addr, text);
                             let mut label = egui::RichText::new(&text).monospace();
                             if addr == current_pc {
                                 label = label.background_color(egui::Color32::from_rgb(50, 50, 80));
                             }
-                            ui.label(label);
+                            if debugger.breakpoints.contains(addr) {
+                                label = label.color(egui::Color32::from_rgb(230, 80, 80));
+                            }
+                            let response = ui.add(egui::Label::new(label).sense(egui::Sense::click()))
+                                .on_hover_text("Click to toggle breakpoint");
+                            if response.clicked() {
+                                debugger.breakpoints.toggle(addr);
+                            }
+                        }
+                    });
+                });
+
+                columns[2].vertical(|ui| {
+                    ui.label(egui::RichText::new("Breakpoints").underline());
+                    ui.horizontal(|ui| {
+                        let input = ui.add(
+                            egui::TextEdit::singleline(&mut debugger.new_breakpoint)
+                                .hint_text("0x200 or label")
+                                .desired_width(100.0),
+                        );
+                        let submitted = input.lost_focus() && ui.input(|i| i.key_pressed(egui::Key::Enter));
+                        let address = resolve_address(&debugger.new_breakpoint, &debugger.symbols);
+                        if (ui.add_enabled(address.is_some(), egui::Button::new("Add")).clicked() || submitted)
+                            && let Some(address) = address
+                        {
+                            debugger.breakpoints.insert(address);
+                            debugger.new_breakpoint.clear();
+                        }
+                        if ui.add_enabled(!debugger.breakpoints.is_empty(), egui::Button::new("Clear")).clicked() {
+                            debugger.breakpoints.clear();
+                        }
+                    });
+                    egui::ScrollArea::vertical().id_salt("breakpoint_list").show(ui, |ui| {
+                        let mut removed = None;
+                        for addr in debugger.breakpoints.iter() {
+                            ui.horizontal(|ui| {
+                                ui.monospace(debugger.symbols.format_address(addr))
+                                    .on_hover_text(format!("{:#06X}", addr));
+                                if ui.small_button("✖").clicked() {
+                                    removed = Some(addr);
+                                }
+                            });
+                        }
+                        if let Some(addr) = removed {
+                            debugger.breakpoints.remove(addr);
+                        }
+                    });
+
+                    ui.separator();
+                    ui.label(egui::RichText::new("Watchpoints").underline());
+                    ui.horizontal(|ui| {
+                        egui::ComboBox::from_id_salt("watch_type")
+                            .selected_text(debugger.watch_type.name())
+                            .show_ui(ui, |ui| {
+                                for watch_type in WatchType::ALL {
+                                    ui.selectable_value(&mut debugger.watch_type, watch_type, watch_type.name());
+                                }
+                            });
+                        let input = ui.add(
+                            egui::TextEdit::singleline(&mut debugger.new_watchpoint)
+                                .hint_text(debugger.watch_type.hint())
+                                .desired_width(140.0),
+                        );
+                        let submitted = input.lost_focus() && ui.input(|i| i.key_pressed(egui::Key::Enter));
+                        if ui.button("Add").clicked() || submitted {
+                            match parse_watchpoint(debugger.watch_type, &debugger.new_watchpoint, &*chip8, &debugger.symbols) {
+                                Ok(watchpoint) => {
+                                    debugger.breakpoints.add_watchpoint(watchpoint);
+                                    debugger.new_watchpoint.clear();
+                                    debugger.watch_error = None;
+                                }
+                                Err(err) => debugger.watch_error = Some(err),
+                            }
+                        }
+                    });
+                    if let Some(err) = &debugger.watch_error {
+                        ui.colored_label(egui::Color32::from_rgb(230, 80, 80), err);
+                    }
+                    egui::ScrollArea::vertical().id_salt("watchpoint_list").show(ui, |ui| {
+                        let mut removed = None;
+                        for (index, watchpoint) in debugger.breakpoints.watchpoints_mut().iter_mut().enumerate() {
+                            ui.horizontal(|ui| {
+                                ui.checkbox(&mut watchpoint.enabled, "");
+                                ui.monospace(&watchpoint.label);
+                                if ui.small_button("✖").clicked() {
+                                    removed = Some(index);
+                                }
+                            });
+                        }
+                        if let Some(index) = removed {
+                            debugger.breakpoints.remove_watchpoint(index);
                         }
                     });
                 });
+
+                columns[3].vertical(|ui| {
+                    draw_call_stack(ui, chip8, &debugger.symbols);
+                    ui.separator();
+                    draw_timers(ui, chip8);
+                    ui.separator();
+                    draw_keypad(ui, chip8);
+                });
             });
         });
 }
 
+// Fallback for code outside the analysed ROM, e.g. the font area or code the
+// program wrote to memory: a fixed window of instructions around PC.
+fn raw_disassembly(chip8: &Chip8, symbols: &SymbolTable) -> Vec<(Gutter, String)> {
+    let mut rows = Vec::new();
+    for offset in -6..=7 {
+        let addr = chip8.pc.wrapping_add_signed(offset * 2);
+        if addr as usize > chip8.memory.len() - 2 {
+            continue;
+        }
+        let opcode = ((chip8.memory[addr as usize] as u16) << 8) | chip8.memory[addr as usize + 1] as u16;
+        if let Some(label) = symbols.name(addr) {
+            rows.push((Gutter::Label { address: addr }, format!("{}:", label)));
+        }
+        rows.push((Gutter::Code { address: addr, opcode, length: 2 }, symbols.disassemble(opcode)));
+    }
+    rows
+}
+
+fn draw_call_stack(ui: &mut egui::Ui, chip8: &Chip8, symbols: &SymbolTable) {
+    ui.label(egui::RichText::new("Call Stack").underline());
+    let depth = (chip8.sp as usize).min(chip8::STACK_SIZE);
+    if depth == 0 {
+        ui.weak("(empty)");
+        return;
+    }
+
+    egui::ScrollArea::vertical().id_salt("call_stack").max_height(100.0).show(ui, |ui| {
+        for level in (0..depth).rev() {
+            let caller = chip8.stack[level];
+            let call = match chip8.memory.get(caller as usize..caller as usize + 2) {
+                Some(&[hi, lo]) => symbols.disassemble(((hi as u16) << 8) | lo as u16),
+                _ => "???".to_string(),
+            };
+            let return_address = caller.wrapping_add(2);
+            ui.monospace(format!("#{:X} {}: {}", level, symbols.format_address(caller), call))
+                .on_hover_text(format!("Returns to {} ({:#06X})", symbols.format_address(return_address), return_address));
+        }
+    });
+}
+
+fn draw_timers(ui: &mut egui::Ui, chip8: &Chip8) {
+    ui.label(egui::RichText::new("Timers").underline());
+    for (name, value) in [("DT", chip8.delay_timer), ("ST", chip8.sound_timer)] {
+        ui.horizontal(|ui| {
+            ui.label(name);
+            ui.add(
+                egui::ProgressBar::new(value as f32 / u8::MAX as f32)
+                    .desired_width(100.0)
+                    .text(format!("{value}")),
+            );
+            if name == "ST" && value > 0 {
+                ui.label("🔊");
+            }
+        });
+    }
+}
+
+fn draw_keypad(ui: &mut egui::Ui, chip8: &Chip8) {
+    const LAYOUT: [[usize; 4]; 4] = [
+        [0x1, 0x2, 0x3, 0xC],
+        [0x4, 0x5, 0x6, 0xD],
+        [0x7, 0x8, 0x9, 0xE],
+        [0xA, 0x0, 0xB, 0xF],
+    ];
+
+    ui.label(egui::RichText::new("Keypad").underline());
+    egui::Grid::new("keypad_grid").spacing([2.0, 2.0]).show(ui, |ui| {
+        for row in LAYOUT {
+            for key in row {
+                let held = chip8.keypad[key] != 0;
+                let fill = if held { egui::Color32::from_rgb(100, 255, 100) } else { egui::Color32::from_gray(40) };
+                let text_color = if held { egui::Color32::BLACK } else { egui::Color32::GRAY };
+                let label = egui::RichText::new(format!("{:X}", key)).monospace().color(text_color);
+                ui.add_sized([20.0, 20.0], egui::Button::new(label).fill(fill).sense(egui::Sense::hover()));
+            }
+            ui.end_row();
+        }
+    });
+}
+
+fn parse_address(text: &str) -> Option<u16> {
+    let text = text.trim();
+    let digits = text.strip_prefix("0x").or_else(|| text.strip_prefix("0X")).unwrap_or(text);
+    u16::from_str_radix(digits, 16).ok()
+}
+
+fn resolve_address(text: &str, symbols: &SymbolTable) -> Option<u16> {
+    symbols.resolve(text).or_else(|| parse_address(text))
+}
+
+fn register_value(ui: &mut egui::Ui, chip8: &mut Chip8, debugger: &mut DebuggerState, editable: bool, field: RegisterField) {
+    let (value, max, digits) = match field {
+        RegisterField::V(reg) => (chip8.v[reg] as u32, u8::MAX as u32, 2),
+        RegisterField::I => (chip8.i as u32, u16::MAX as u32, 4),
+        RegisterField::Pc => (chip8.pc as u32, chip8.memory.len() as u32 - 2, 4),
+        RegisterField::Sp => (chip8.sp as u32, chip8::STACK_SIZE as u32, 2),
+        RegisterField::DelayTimer => (chip8.delay_timer as u32, u8::MAX as u32, 2),
+        RegisterField::SoundTimer => (chip8.sound_timer as u32, u8::MAX as u32, 2),
+        RegisterField::Stack(slot) => (chip8.stack[slot] as u32, chip8.memory.len() as u32 - 2, 4),
+    };
+
+    if let Some((editing, text)) = debugger.editing.as_mut()
+        && *editing == field
+    {
+        let response = ui.add(
+            egui::TextEdit::singleline(text)
+                .desired_width(digits as f32 * 9.0 + 18.0)
+                .font(egui::TextStyle::Monospace),
+        );
+        response.request_focus();
+        if ui.input(|i| i.key_pressed(egui::Key::Enter)) {
+            match parse_value(text, max) {
+                Ok(value) => {
+                    set_register(chip8, field, value);
+                    debugger.edit_error = None;
+                }
+                Err(err) => debugger.edit_error = Some(err),
+            }
+            debugger.editing = None;
+        } else if ui.input(|i| i.key_pressed(egui::Key::Escape)) {
+            debugger.editing = None;
+        }
+        return;
+    }
+
+    let text = format!("{:#0width$X}", value, width = digits + 2);
+    let response = ui.add(egui::Label::new(egui::RichText::new(&text).monospace()).sense(egui::Sense::click()));
+    if editable && response.on_hover_text("Click to edit (hex with 0x, otherwise decimal)").clicked() {
+        debugger.editing = Some((field, text));
+    }
+}
+
+fn set_register(chip8: &mut Chip8, field: RegisterField, value: u32) {
+    match field {
+        RegisterField::V(reg) => chip8.v[reg] = value as u8,
+        RegisterField::I => chip8.i = value as u16,
+        RegisterField::Pc => chip8.pc = value as u16,
+        RegisterField::Sp => chip8.sp = value as u8,
+        RegisterField::DelayTimer => chip8.delay_timer = value as u8,
+        RegisterField::SoundTimer => chip8.sound_timer = value as u8,
+        RegisterField::Stack(slot) => chip8.stack[slot] = value as u16,
+    }
+    chip8.journal.clear();
+}
+
+fn parse_value(text: &str, max: u32) -> Result<u32, String> {
+    let text = text.trim();
+    let value = match text.strip_prefix("0x").or_else(|| text.strip_prefix("0X")) {
+        Some(hex) => u32::from_str_radix(hex, 16),
+        None => text.parse(),
+    }
+    .map_err(|_| format!("'{}' is not a number (use 0x for hex)", text))?;
+
+    if value > max {
+        return Err(format!("{} is out of range (max {:#X})", text, max));
+    }
+    Ok(value)
+}
+
+fn parse_watchpoint(
+    watch_type: WatchType,
+    text: &str,
+    chip8: &Chip8,
+    symbols: &SymbolTable,
+) -> Result<Watchpoint, String> {
+    let access = match watch_type {
+        WatchType::Read => WatchAccess::Read,
+        WatchType::Write => WatchAccess::Write,
+        WatchType::Access => WatchAccess::Any,
+        WatchType::Change => return Watchpoint::changed(text, chip8).map_err(|err| err.to_string()),
+        WatchType::Condition => return Watchpoint::condition(text, chip8).map_err(|err| err.to_string()),
+        WatchType::Opcode => {
+            return Watchpoint::opcode(text).ok_or_else(|| "expected a pattern like DXYN or FX33".to_string());
+        }
+    };
+
+    let (start, end) = text.split_once('-').unwrap_or((text, text));
+    match (resolve_address(start, symbols), resolve_address(end, symbols)) {
+        (Some(start), Some(end)) if start <= end => Ok(Watchpoint::memory(start as usize..=end as usize, access)),
+        _ => Err("expected an address or range like 0x300-0x302".to_string()),
+    }
+}
+
+pub fn draw_memory_panel(chip8: &mut Chip8, view: &mut MemoryViewState, editable: bool, ctx: &egui::Context) {
+    const BYTES_PER_ROW: usize = 16;
+
+    let mut open = view.open;
+    egui::Window::new("Memory")
+        .open(&mut open)
+        .default_width(560.0)
+        .default_height(300.0)
+        .show(ctx, |ui| {
+            ui.horizontal(|ui| {
+                ui.label("Go to");
+                let input = ui.add(egui::TextEdit::singleline(&mut view.goto).hint_text("0x200").desired_width(60.0));
+                let submitted = input.lost_focus() && ui.input(|i| i.key_pressed(egui::Key::Enter));
+                if (ui.button("Go").clicked() || submitted)
+                    && let Some(address) = parse_address(&view.goto)
+                {
+                    view.scroll_to = Some(address as usize);
+                }
+                if ui.button("PC").clicked() {
+                    view.scroll_to = Some(chip8.pc as usize);
+                }
+                if ui.button("I").clicked() {
+                    view.scroll_to = Some(chip8.i as usize);
+                }
+                if !editable {
+                    ui.weak("Pause to edit");
+                }
+            });
+            if let Some(err) = &view.edit_error {
+                ui.colored_label(egui::Color32::from_rgb(230, 80, 80), err);
+            }
+            ui.separator();
+
+            let row_height = ui.text_style_height(&egui::TextStyle::Monospace) + ui.spacing().item_spacing.y;
+            let rows = chip8.memory.len().div_ceil(BYTES_PER_ROW);
+            let mut scroll_area = egui::ScrollArea::vertical().auto_shrink(false);
+            if let Some(address) = view.scroll_to.take() {
+                let row = address.min(chip8.memory.len().saturating_sub(1)) / BYTES_PER_ROW;
+                scroll_area = scroll_area.vertical_scroll_offset(row as f32 * row_height);
+            }
+
+            scroll_area.show_rows(ui, row_height, rows, |ui, row_range| {
+                for row in row_range {
+                    let start = row * BYTES_PER_ROW;
+                    let end = (start + BYTES_PER_ROW).min(chip8.memory.len());
+                    ui.horizontal(|ui| {
+                        ui.spacing_mut().item_spacing.x = 4.0;
+                        ui.monospace(format!("{:#06X}:", start));
+                        for address in start..end {
+                            draw_memory_byte(ui, chip8, view, editable, address);
+                        }
+                        let ascii: String = chip8.memory[start..end]
+                            .iter()
+                            .map(|&byte| if byte.is_ascii_graphic() { byte as char } else { '.' })
+                            .collect();
+                        ui.monospace(format!(" |{}|", ascii));
+                    });
+                }
+            });
+        });
+    view.open = open;
+}
+
+fn draw_memory_byte(ui: &mut egui::Ui, chip8: &mut Chip8, view: &mut MemoryViewState, editable: bool, address: usize) {
+    if let Some((editing, text)) = view.editing.as_mut()
+        && *editing == address
+    {
+        let response = ui.add(egui::TextEdit::singleline(text).char_limit(2).desired_width(16.0).font(egui::TextStyle::Monospace));
+        response.request_focus();
+        if ui.input(|i| i.key_pressed(egui::Key::Enter)) {
+            match u8::from_str_radix(text.trim(), 16) {
+                Ok(value) => {
+                    chip8.memory[address] = value;
+                    chip8.journal.clear();
+                    view.edit_error = None;
+                }
+                Err(_) => view.edit_error = Some(format!("'{}' is not a hex byte, {:#06X} unchanged", text.trim(), address)),
+            }
+            view.editing = None;
+        } else if ui.input(|i| i.key_pressed(egui::Key::Escape)) {
+            view.editing = None;
+        }
+        return;
+    }
+
+    let pc = chip8.pc as usize;
+    let mut text = egui::RichText::new(format!("{:02X}", chip8.memory[address])).monospace();
+    if address == pc || address == pc + 1 {
+        text = text.background_color(egui::Color32::from_rgb(50, 50, 80));
+    } else if address == chip8.i as usize {
+        text = text.background_color(egui::Color32::from_rgb(40, 80, 40));
+    }
+    let age = view.recent_writes.get(address).copied().unwrap_or(0);
+    if age > 0 {
+        let strength = age as f32 / RECENT_WRITE_FRAMES as f32;
+        text = text.color(egui::Color32::from_rgb(255, 140, 60).gamma_multiply(0.4 + 0.6 * strength));
+    }
+
+    let response = ui.add(egui::Label::new(text).sense(egui::Sense::click()));
+    if editable && response.clicked() {
+        view.editing = Some((address, format!("{:02X}", chip8.memory[address])));
+    }
+}
+
 pub fn draw_emulator_screen(chip8: &Chip8, ctx: &egui::Context) {
     egui::CentralPanel::default().show(ctx, |ui| {
         let (response, painter) =
             ui.allocate_painter(ui.available_size_before_wrap(), egui::Sense::hover());
-        let pixel_size_x = response.rect.width() / chip8::DISPLAY_WIDTH as f32;
-        let pixel_size_y = response.rect.height() / chip8::DISPLAY_HEIGHT as f32;
+        let width = chip8.display_width();
+        let height = chip8.display_height();
+        let pixel_size_x = response.rect.width() / width as f32;
+        let pixel_size_y = response.rect.height() / height as f32;
         let pixel_size = pixel_size_x.min(pixel_size_y);
         painter.rect_filled(response.rect, 0.0, egui::Color32::BLACK);
-        for y in 0..chip8::DISPLAY_HEIGHT {
-            for x in 0..chip8::DISPLAY_WIDTH {
-                if chip8.display[y * chip8::DISPLAY_WIDTH + x] != 0 {
+        for y in 0..height {
+            for x in 0..width {
+                let pixel = chip8.display[y * width + x] as usize;
+                if pixel != 0 {
                     let rect = egui::Rect::from_min_size(
                         response.rect.min + egui::vec2(x as f32 * pixel_size, y as f32 * pixel_size),
                         egui::vec2(pixel_size, pixel_size),
                     );
-                    painter.rect_filled(rect, 0.0, egui::Color32::from_rgb(100, 255, 100));
+                    let [r, g, b] = chip8::DISPLAY_PALETTE[pixel & 0x3];
+                    painter.rect_filled(rect, 0.0, egui::Color32::from_rgb(r, g, b));
                 }
             }
         }
     });
-}
\ No newline at end of file
+}
+
+pub fn draw_profiler_panel(app: &mut MyApp, ctx: &egui::Context) {
+    const HOT_ROWS: usize = 32;
+
+    let mut open = app.profiler_view.open;
+    egui::Window::new("Profiler")
+        .open(&mut open)
+        .default_width(720.0)
+        .default_height(360.0)
+        .show(ctx, |ui| {
+            ui.horizontal(|ui| {
+                ui.checkbox(&mut app.profiler_view.recording, "Record");
+                if ui.button("Reset").clicked() {
+                    app.profiler.reset();
+                }
+                if ui.button("Export CSV...").clicked()
+                    && let Some(path) = FileDialog::new().add_filter("CSV", &["csv"]).save_file()
+                {
+                    app.export_profile(&path, false);
+                }
+                if ui.button("Export Folded Stacks...").clicked()
+                    && let Some(path) = FileDialog::new().add_filter("Folded stacks", &["folded", "txt"]).save_file()
+                {
+                    app.export_profile(&path, true);
+                }
+            });
+
+            let frames: Vec<u64> = app.profiler.frame_cycles().collect();
+            ui.horizontal(|ui| {
+                ui.label(format!("{} cycles", app.profiler.total()));
+                if let (Some(last), Some(max)) = (frames.last(), frames.iter().max()) {
+                    let average = frames.iter().sum::<u64>() as f32 / frames.len() as f32;
+                    ui.label(format!("Cycles/frame: {last} (avg {average:.1}, max {max})"));
+                }
+            });
+            draw_frame_cycles(ui, &frames);
+            ui.separator();
+
+            let profiler = &app.profiler;
+            let symbols = &app.debugger.symbols;
+            ui.columns(3, |columns| {
+                columns[0].label(egui::RichText::new("Hot Addresses").underline());
+                egui::ScrollArea::vertical().id_salt("profiler_hot").show(&mut columns[0], |ui| {
+                    egui::Grid::new("profiler_hot_grid").striped(true).show(ui, |ui| {
+                        for (address, opcode, count) in profiler.hot_addresses().into_iter().take(HOT_ROWS) {
+                            ui.monospace(symbols.format_address(address)).on_hover_text(format!("{:#06X}", address));
+                            ui.monospace(symbols.disassemble(opcode));
+                            ui.monospace(format!("{:5.1}%", profiler.percent(count)));
+                            ui.end_row();
+                        }
+                    });
+                });
+
+                columns[1].label(egui::RichText::new("Subroutines").underline());
+                egui::ScrollArea::vertical().id_salt("profiler_subroutines").show(&mut columns[1], |ui| {
+                    egui::Grid::new("profiler_subroutine_grid").striped(true).show(ui, |ui| {
+                        ui.weak("Name");
+                        ui.weak("Self");
+                        ui.weak("Total");
+                        ui.end_row();
+                        for subroutine in profiler.subroutines() {
+                            ui.monospace(profiler::subroutine_name(subroutine.address, symbols));
+                            ui.monospace(format!("{:5.1}%", profiler.percent(subroutine.exclusive)));
+                            ui.monospace(format!("{:5.1}%", profiler.percent(subroutine.inclusive)));
+                            ui.end_row();
+                        }
+                    });
+                });
+
+                columns[2].label(egui::RichText::new("Opcode Classes").underline());
+                egui::ScrollArea::vertical().id_salt("profiler_classes").show(&mut columns[2], |ui| {
+                    egui::Grid::new("profiler_class_grid").striped(true).show(ui, |ui| {
+                        for (class, count) in profiler.opcode_classes() {
+                            ui.monospace(class);
+                            ui.monospace(count.to_string());
+                            ui.monospace(format!("{:5.1}%", profiler.percent(count)));
+                            ui.end_row();
+                        }
+                    });
+                });
+            });
+        });
+    app.profiler_view.open = open;
+}
+
+fn draw_frame_cycles(ui: &mut egui::Ui, frames: &[u64]) {
+    let size = egui::vec2(profiler::FRAME_HISTORY as f32 * 3.0, 40.0);
+    let (rect, _) = ui.allocate_exact_size(size, egui::Sense::hover());
+    let painter = ui.painter_at(rect);
+    painter.rect_filled(rect, 0.0, egui::Color32::from_gray(30));
+
+    let max = frames.iter().copied().max().unwrap_or(0).max(1) as f32;
+    for (index, &cycles) in frames.iter().enumerate() {
+        let height = rect.height() * cycles as f32 / max;
+        let left = rect.left() + index as f32 * 3.0;
+        let bar = egui::Rect::from_min_max(egui::pos2(left, rect.bottom() - height), egui::pos2(left + 2.0, rect.bottom()));
+        painter.rect_filled(bar, 0.0, egui::Color32::from_rgb(100, 160, 230));
+    }
+}
+
+pub fn draw_fault_dialog(app: &mut MyApp, ctx: &egui::Context) {
+    let Some(message) = app.fault.clone() else {
+        return;
+    };
+
+    egui::Window::new("Emulator Fault")
+        .collapsible(false)
+        .resizable(false)
+        .anchor(egui::Align2::CENTER_CENTER, [0.0, 0.0])
+        .show(ctx, |ui| {
+            ui.label(message);
+            ui.add_space(5.0);
+            if ui.button("OK").clicked() {
+                app.fault = None;
+            }
+        });
+}
@@ -0,0 +1,144 @@
+use chip8_emulator::assembler;
+use chip8_emulator::disasm;
+use chip8_emulator::listing::{self, RomListing, Syntax};
+use chip8_emulator::octo;
+use rand::rngs::StdRng;
+use rand::{Rng, SeedableRng};
+
+#[test]
+fn every_instruction_round_trips() {
+    for opcode in 0..=u16::MAX {
+        if listing::instruction_length(opcode) != Some(2) {
+            continue;
+        }
+        let source = disasm::disassemble(opcode);
+        let bytes = assembler::assemble(&source).unwrap_or_else(|err| panic!("{opcode:04X} `{source}`: {err}"));
+        assert_eq!(bytes, opcode.to_be_bytes(), "{opcode:04X} `{source}`");
+    }
+}
+
+#[test]
+fn rom_listing_round_trips() {
+    let mut rng = StdRng::seed_from_u64(0xC8);
+    for _ in 0..50 {
+        let len = rng.random_range(1..=512);
+        let rom: Vec<u8> = (0..len).map(|_| rng.random()).collect();
+        let source = RomListing::analyze(&rom).render(Syntax::Cowgod);
+        let bytes = assembler::assemble(&source).unwrap_or_else(|err| panic!("{err}\n{source}"));
+        assert_eq!(bytes, rom, "{source}");
+    }
+}
+
+#[test]
+fn octo_listing_round_trips() {
+    let mut rng = StdRng::seed_from_u64(0xC8);
+    for _ in 0..50 {
+        let len = rng.random_range(1..=512);
+        let rom: Vec<u8> = (0..len).map(|_| rng.random()).collect();
+        let source = RomListing::analyze(&rom).render(Syntax::Octo);
+        let program = octo::compile(&source).unwrap_or_else(|err| panic!("{err}\n{source}"));
+        assert_eq!(program.rom, rom, "{source}");
+    }
+}
+
+fn assemble(source: &str) -> Vec<u8> {
+    assembler::assemble(source).unwrap_or_else(|err| panic!("{err}\n{source}"))
+}
+
+fn assemble_error(source: &str) -> String {
+    match assembler::assemble(source) {
+        Ok(bytes) => panic!("assembled to {bytes:02X?}\n{source}"),
+        Err(err) => err.to_string(),
+    }
+}
+
+#[test]
+fn labels_resolve_forward_and_backward() {
+    let source = "\
+start:
+    CALL sub        ; forward reference
+    JP   start
+sub: LD V0, value
+a: b: RET
+value equ 0x2A
+";
+    assert_eq!(assemble(source), [0x22, 0x04, 0x12, 0x00, 0x60, 0x2A, 0x00, 0xEE]);
+    assert_eq!(assemble("JP b\na: b: CLS"), [0x12, 0x02, 0x00, 0xE0]);
+}
+
+#[test]
+fn constants_and_expressions() {
+    let source = "\
+base  equ 0x300
+next  equ base + 2
+count equ end - data
+    LD   I, next - 1
+    LD   V1, count
+    ADD  V1, -1 + 2
+    LD   V2, 0b1010
+data:
+    db 1, 2
+end:
+";
+    assert_eq!(assemble(source), [0xA3, 0x01, 0x61, 0x02, 0x71, 0x01, 0x62, 0x0A, 0x01, 0x02]);
+}
+
+#[test]
+fn data_directives() {
+    let source = "\
+    LD   I, sprite
+sprite:
+    db 0xF0, 0x90, 255
+    dw 0x1234, sprite
+    LD   I, LONG table
+table:
+    dw 0xFFFF
+";
+    assert_eq!(
+        assemble(source),
+        [0xA2, 0x02, 0xF0, 0x90, 0xFF, 0x12, 0x34, 0x02, 0x02, 0xF0, 0x00, 0x02, 0x0D, 0xFF, 0xFF]
+    );
+}
+
+#[test]
+fn includes_resolve_relative_to_the_including_file() {
+    let dir = std::env::temp_dir().join(format!("chip8-asm-{}", std::process::id()));
+    std::fs::create_dir_all(dir.join("lib")).unwrap();
+    std::fs::write(dir.join("main.asm"), "    CALL sub\n    JP 0x200\ninclude \"lib/sub.asm\"\n").unwrap();
+    std::fs::write(dir.join("lib/sub.asm"), "sub:\n    include data.asm\n    RET\n").unwrap();
+    std::fs::write(dir.join("lib/data.asm"), "    LD V0, 7\n").unwrap();
+    std::fs::write(dir.join("broken.asm"), "    CLS\n    include missing.asm\n").unwrap();
+
+    let rom = assembler::assemble_file(dir.join("main.asm"));
+    let err = assembler::assemble_file(dir.join("broken.asm")).unwrap_err();
+    std::fs::remove_dir_all(&dir).unwrap();
+
+    assert_eq!(rom.unwrap(), [0x22, 0x04, 0x12, 0x00, 0x60, 0x07, 0x00, 0xEE]);
+    assert_eq!((err.file.ends_with("broken.asm"), err.line), (true, 2));
+    assert!(err.message.starts_with("cannot include"), "{err}");
+}
+
+#[test]
+fn symbol_errors() {
+    assert_eq!(assemble_error("start: CLS\nstart: RET"), "<input>:2: 'start' is already defined");
+    assert_eq!(assemble_error("x equ 1\nx: CLS"), "<input>:2: 'x' is already defined");
+    assert_eq!(assemble_error("CLS\nJP nowhere"), "<input>:2: undefined symbol 'nowhere'");
+    assert_eq!(assemble_error("a equ b\nb equ a\nLD V0, a"), "<input>:3: constant 'a' is defined in terms of itself");
+    assert_eq!(assemble_error("1x equ 2"), "<input>:1: invalid constant name '1x'");
+}
+
+#[test]
+fn range_and_syntax_errors() {
+    assert_eq!(assemble_error("LD V0, 256"), "<input>:1: value 256 is out of range (0 to 0xFF)");
+    assert_eq!(assemble_error("JP 0x1000"), "<input>:1: value 4096 is out of range (0 to 0xFFF)");
+    assert_eq!(assemble_error("DRW V0, V1, 16"), "<input>:1: value 16 is out of range (0 to 0xF)");
+    assert_eq!(assemble_error("db -1"), "<input>:1: value -1 is out of range (0 to 0xFF)");
+    assert_eq!(assemble_error("dw 0x10000"), "<input>:1: value 65536 is out of range (0 to 0xFFFF)");
+    assert_eq!(assemble_error("LD V0, 0xZZ"), "<input>:1: invalid number '0xZZ'");
+    assert_eq!(assemble_error("SKP 3"), "<input>:1: expected a register, found '3'");
+    assert_eq!(assemble_error("FOO V0"), "<input>:1: invalid instruction 'FOO V0'");
+    assert_eq!(assemble_error("include"), "<input>:1: include needs a file name");
+
+    let too_big = format!("db {}", vec!["0"; 0x10000 - 0x200 + 1].join(", "));
+    assert_eq!(assemble_error(&too_big), "<input>:1: program does not fit in memory");
+}
@@ -0,0 +1,83 @@
+use chip8_emulator::audio::{AudioGenerator, AudioOutput, WavOutput};
+use chip8_emulator::chip8::Chip8;
+
+#[test]
+fn beeper_is_a_square_wave_at_the_set_frequency() {
+    let mut chip8 = Chip8::new();
+    let mut generator = AudioGenerator::new();
+    generator.settings.frequency = 441.0;
+    let volume = generator.settings.volume;
+    let mut samples = [0.0; 400];
+
+    generator.generate(&chip8, 44100, &mut samples);
+    assert!(samples.iter().all(|&sample| sample == 0.0), "silent while the sound timer is zero");
+
+    chip8.sound_timer = 10;
+    generator.generate(&chip8, 44100, &mut samples);
+    assert!(samples[..50].iter().all(|&sample| sample == volume));
+    assert!(samples[50..100].iter().all(|&sample| sample == -volume));
+    assert!(samples[100..150].iter().all(|&sample| sample == volume));
+
+    generator.settings.muted = true;
+    generator.generate(&chip8, 44100, &mut samples);
+    assert!(samples.iter().all(|&sample| sample == 0.0), "silent while muted");
+}
+
+#[test]
+fn xo_pattern_plays_at_pitch_rate() {
+    assert_eq!(AudioGenerator::playback_rate(64), 4000.0);
+    assert_eq!(AudioGenerator::playback_rate(112), 8000.0);
+    assert_eq!(AudioGenerator::playback_rate(16), 2000.0);
+
+    let mut chip8 = Chip8::new();
+    chip8.sound_timer = 10;
+    chip8.pitch = 64;
+    let mut pattern = [0x00; 16];
+    pattern[0] = 0xF0;
+    chip8.audio_pattern = Some(pattern);
+
+    let mut generator = AudioGenerator::new();
+    let volume = generator.settings.volume;
+
+    // One sample per bit at 4000 Hz, wrapping after the 128-bit pattern.
+    let mut samples = [0.0; 132];
+    generator.generate(&chip8, 4000, &mut samples);
+    assert_eq!(samples[..4], [volume; 4]);
+    assert!(samples[4..128].iter().all(|&sample| sample == -volume));
+    assert_eq!(samples[128..], [volume; 4]);
+
+    // Twice the pitch rate plays each bit for half a sample.
+    let mut generator = AudioGenerator::new();
+    chip8.pitch = 112;
+    let mut samples = [0.0; 4];
+    generator.generate(&chip8, 4000, &mut samples);
+    assert_eq!(samples, [volume, volume, -volume, -volume]);
+}
+
+#[test]
+fn wav_header_matches_written_samples() {
+    let path = std::env::temp_dir().join(format!("chip8_audio_{}.wav", std::process::id()));
+    let mut output = WavOutput::create(&path, 22050).unwrap();
+    output.write_samples(&[0.0, 1.0, -1.0]);
+    drop(output);
+
+    let bytes = std::fs::read(&path).unwrap();
+    std::fs::remove_file(&path).unwrap();
+    let u16_at = |offset: usize| u16::from_le_bytes([bytes[offset], bytes[offset + 1]]);
+    let u32_at = |offset: usize| u32::from_le_bytes(bytes[offset..offset + 4].try_into().unwrap());
+
+    assert_eq!(bytes.len(), 44 + 6);
+    assert_eq!(&bytes[0..4], b"RIFF");
+    assert_eq!(u32_at(4), 36 + 6);
+    assert_eq!(&bytes[8..16], b"WAVEfmt ");
+    assert_eq!(u32_at(16), 16);
+    assert_eq!(u16_at(20), 1, "PCM");
+    assert_eq!(u16_at(22), 1, "mono");
+    assert_eq!(u32_at(24), 22050);
+    assert_eq!(u32_at(28), 44100, "byte rate");
+    assert_eq!(u16_at(32), 2, "block align");
+    assert_eq!(u16_at(34), 16, "bits per sample");
+    assert_eq!(&bytes[36..40], b"data");
+    assert_eq!(u32_at(40), 6);
+    assert_eq!(bytes[44..], [0x00, 0x00, 0xFF, 0x7F, 0x01, 0x80]);
+}
@@ -0,0 +1,186 @@
+use chip8_emulator::breakpoints::{Breakpoints, OpcodePattern, WatchAccess, Watchpoint};
+use chip8_emulator::chip8::{AccessKind, Chip8};
+
+#[test]
+fn condition_triggers_when_it_becomes_true() {
+    let mut chip8 = Chip8::new();
+    let mut breakpoints = Breakpoints::default();
+    breakpoints.add_watchpoint(Watchpoint::condition("V0 >= 2", &chip8).unwrap());
+
+    let mut hits = Vec::new();
+    for value in [0, 1, 2, 3, 4, 0, 5, 6] {
+        chip8.v[0] = value;
+        hits.push(breakpoints.check(&chip8).is_some());
+    }
+    assert_eq!(hits, [false, false, true, false, false, false, true, false]);
+}
+
+#[test]
+fn condition_already_true_when_added_does_not_trigger() {
+    let mut chip8 = Chip8::new();
+    chip8.v[0] = 3;
+    let mut breakpoints = Breakpoints::default();
+    breakpoints.add_watchpoint(Watchpoint::condition("V0 == 3", &chip8).unwrap());
+
+    assert_eq!(breakpoints.check(&chip8), None);
+    chip8.v[0] = 0;
+    assert_eq!(breakpoints.check(&chip8), None);
+    chip8.v[0] = 3;
+    assert_eq!(breakpoints.check(&chip8).as_deref(), Some("if V0 == 3"));
+}
+
+#[test]
+fn resuming_from_a_stalled_instruction_does_not_break_again() {
+    // LD V0, 1; LD V1, K; DRW V0, V0, 1; JP 0x200
+    let mut chip8 = Chip8::new();
+    chip8.quirks.display_wait = true;
+    chip8.load_rom_bytes(&[0x60, 0x01, 0xF1, 0x0A, 0xD0, 0x01, 0x12, 0x00]).unwrap();
+    let mut breakpoints = Breakpoints::default();
+    breakpoints.insert(0x202);
+    breakpoints.insert(0x204);
+
+    chip8.emulate_cycle().unwrap();
+    assert_eq!(breakpoints.check(&chip8).as_deref(), Some("breakpoint at 0x0202"));
+    for _ in 0..5 {
+        chip8.emulate_cycle().unwrap();
+        assert_eq!(chip8.pc, 0x202);
+        assert_eq!(breakpoints.check(&chip8), None);
+    }
+
+    chip8.keypad[5] = 1;
+    chip8.emulate_cycle().unwrap();
+    assert_eq!(breakpoints.check(&chip8).as_deref(), Some("breakpoint at 0x0204"));
+    for _ in 0..5 {
+        chip8.emulate_cycle().unwrap();
+        assert_eq!(breakpoints.check(&chip8), None);
+    }
+
+    chip8.tick_timers();
+    chip8.emulate_cycle().unwrap();
+    assert_eq!(breakpoints.check(&chip8), None);
+    chip8.emulate_cycle().unwrap();
+    chip8.emulate_cycle().unwrap();
+    assert_eq!(breakpoints.check(&chip8).as_deref(), Some("breakpoint at 0x0202"));
+}
+
+// LD I, 0x300; V0 = 7; V1 = 8; LD [I], V1; LD V1, [I]; DRW V0, V1, 2; LD B, V0; SAVE V0 - V1; JP 0x210
+const ACCESSES: [u8; 18] = [
+    0xA3, 0x00, 0x60, 0x07, 0x61, 0x08, 0xF1, 0x55, 0xF1, 0x65, 0xD0, 0x12, 0xF0, 0x33, 0x50, 0x12, 0x12, 0x10,
+];
+
+fn accesses(chip8: &Chip8) -> Vec<(usize, AccessKind)> {
+    let cycle = chip8.last_cycle.as_ref().unwrap();
+    cycle.accesses.iter().map(|access| (access.address, access.kind)).collect()
+}
+
+fn watch(watchpoint: Watchpoint) -> Vec<Option<String>> {
+    let mut chip8 = Chip8::new();
+    chip8.load_rom_bytes(&ACCESSES).unwrap();
+    let mut breakpoints = Breakpoints::default();
+    breakpoints.add_watchpoint(watchpoint);
+    (0..8)
+        .map(|_| {
+            chip8.emulate_cycle().unwrap();
+            breakpoints.check(&chip8)
+        })
+        .collect()
+}
+
+#[test]
+fn cycles_report_memory_accesses() {
+    use AccessKind::{Read, Write};
+
+    let mut chip8 = Chip8::new();
+    chip8.load_rom_bytes(&ACCESSES).unwrap();
+    let mut reported = Vec::new();
+    for _ in 0..8 {
+        chip8.emulate_cycle().unwrap();
+        reported.push(accesses(&chip8));
+    }
+    assert_eq!(
+        reported,
+        [
+            vec![],
+            vec![],
+            vec![],
+            vec![(0x300, Write), (0x301, Write)],
+            vec![(0x300, Read), (0x301, Read)],
+            vec![(0x300, Read), (0x301, Read)],
+            vec![(0x300, Write), (0x301, Write), (0x302, Write)],
+            vec![(0x300, Write), (0x301, Write)],
+        ]
+    );
+}
+
+#[test]
+fn sprite_reads_cover_every_plane() {
+    // LD I, 0x300; PLANE 3; DRW V0, V0, 2
+    let mut chip8 = Chip8::new();
+    chip8.load_rom_bytes(&[0xA3, 0x00, 0xF3, 0x01, 0xD0, 0x02]).unwrap();
+    for _ in 0..3 {
+        chip8.emulate_cycle().unwrap();
+    }
+    let addresses: Vec<usize> = accesses(&chip8).into_iter().map(|(address, _)| address).collect();
+    assert_eq!(addresses, [0x300, 0x301, 0x302, 0x303]);
+}
+
+#[test]
+fn memory_watchpoints_match_access_kind_and_range() {
+    let hits = |range, access| {
+        watch(Watchpoint::memory(range, access)).into_iter().map(|hit| hit.is_some()).collect::<Vec<_>>()
+    };
+    assert_eq!(hits(0x301..=0x301, WatchAccess::Write), [false, false, false, true, false, false, true, true]);
+    assert_eq!(hits(0x301..=0x301, WatchAccess::Read), [false, false, false, false, true, true, false, false]);
+    assert_eq!(hits(0x2FF..=0x300, WatchAccess::Any), [false, false, false, true, true, true, true, true]);
+    assert_eq!(hits(0x302..=0x3FF, WatchAccess::Any), [false, false, false, false, false, false, true, false]);
+    assert_eq!(hits(0x303..=0x303, WatchAccess::Any), [false; 8]);
+
+    let reasons = watch(Watchpoint::memory(0x301..=0x301, WatchAccess::Any));
+    assert_eq!(reasons[3].as_deref(), Some("write to 0x0301 at 0x0206"));
+    assert_eq!(reasons[4].as_deref(), Some("read of 0x0301 at 0x0208"));
+}
+
+#[test]
+fn opcode_watchpoints_match_patterns() {
+    let reasons = watch(Watchpoint::opcode("dxyn").unwrap());
+    assert_eq!(reasons[5].as_deref(), Some("opcode DXYN (D012) at 0x020A"));
+    assert_eq!(reasons.iter().filter(|hit| hit.is_some()).count(), 1);
+
+    let reasons = watch(Watchpoint::opcode("FX55").unwrap());
+    let hits: Vec<bool> = reasons.iter().map(Option::is_some).collect();
+    assert_eq!(hits, [false, false, false, true, false, false, false, false]);
+
+    let pattern = OpcodePattern::parse("8XY6").unwrap();
+    assert!(pattern.matches(0x8126) && !pattern.matches(0x8127));
+    assert!(OpcodePattern::parse("8XY").is_none());
+    assert!(OpcodePattern::parse("GXYN").is_none());
+}
+
+#[test]
+fn changed_watchpoints_report_old_and_new_values() {
+    let mut chip8 = Chip8::new();
+    let mut breakpoints = Breakpoints::default();
+    breakpoints.add_watchpoint(Watchpoint::changed("[0x300] + V1", &chip8).unwrap());
+
+    assert_eq!(breakpoints.check(&chip8), None);
+    chip8.v[1] = 2;
+    assert_eq!(breakpoints.check(&chip8).as_deref(), Some("change [0x300] + V1: 0x0 -> 0x2"));
+    assert_eq!(breakpoints.check(&chip8), None);
+    chip8.memory[0x300] = 0x10;
+    assert_eq!(breakpoints.check(&chip8).as_deref(), Some("change [0x300] + V1: 0x2 -> 0x12"));
+}
+
+#[test]
+fn disabled_watchpoints_stay_silent_but_keep_tracking() {
+    let mut chip8 = Chip8::new();
+    let mut breakpoints = Breakpoints::default();
+    breakpoints.add_watchpoint(Watchpoint::changed("V0", &chip8).unwrap());
+    breakpoints.watchpoints_mut()[0].enabled = false;
+
+    chip8.v[0] = 1;
+    assert_eq!(breakpoints.check(&chip8), None);
+    breakpoints.watchpoints_mut()[0].enabled = true;
+    assert_eq!(breakpoints.check(&chip8), None);
+    chip8.v[0] = 2;
+    assert_eq!(breakpoints.check(&chip8).as_deref(), Some("change V0: 0x1 -> 0x2"));
+}
@@ -0,0 +1,298 @@
+use chip8_emulator::chip8::{BIG_FONTSET_START, Chip8, PROGRAM_START, SCHIP_BIG_FONTSET};
+use chip8_emulator::error::Chip8Error;
+use chip8_emulator::quirks::{Quirks, QuirksPreset};
+
+#[test]
+fn pc_wraps_at_end_of_xo_memory() {
+    let preset = QuirksPreset::XoChip;
+    let mut chip8 = Chip8::with_memory_size(preset.memory_size());
+    chip8.quirks = preset.quirks();
+    let rom: Vec<u8> = [0x60, 0x00].repeat((preset.memory_size() - PROGRAM_START) / 2);
+    chip8.load_rom_bytes(&rom).unwrap();
+
+    for _ in 0..rom.len() / 2 {
+        chip8.emulate_cycle().unwrap();
+    }
+    assert_eq!(chip8.pc, 0);
+}
+
+#[test]
+fn skip_over_last_instruction_wraps() {
+    let mut chip8 = Chip8::new();
+    chip8.pc = 0xFFE;
+    chip8.memory[0xFFE..].copy_from_slice(&[0x30, 0x00]);
+    chip8.emulate_cycle().unwrap();
+    assert_eq!(chip8.pc, 0x002);
+}
+
+fn run(quirks: Quirks, rom: &[u8], cycles: usize) -> Chip8 {
+    let mut chip8 = Chip8::new();
+    chip8.quirks = quirks;
+    chip8.load_rom_bytes(rom).unwrap();
+    for _ in 0..cycles {
+        chip8.emulate_cycle().unwrap();
+    }
+    chip8
+}
+
+fn quirks(set: impl FnOnce(&mut Quirks)) -> Quirks {
+    let mut quirks = QuirksPreset::Modern.quirks();
+    set(&mut quirks);
+    quirks
+}
+
+fn lit(chip8: &Chip8, x: usize, y: usize) -> bool {
+    chip8.display[y * chip8.display_width() + x] != 0
+}
+
+#[test]
+fn shift_source_follows_quirk() {
+    // V0 = 0x01, V1 = 0x81, SHR V0, V1
+    let rom = [0x60, 0x01, 0x61, 0x81, 0x80, 0x16];
+    let chip8 = run(quirks(|_| {}), &rom, 3);
+    assert_eq!((chip8.v[0], chip8.v[0xF]), (0x00, 1));
+    let chip8 = run(quirks(|quirks| quirks.shift_uses_vy = true), &rom, 3);
+    assert_eq!((chip8.v[0], chip8.v[0xF]), (0x40, 1));
+
+    // V0 = 0x01, V1 = 0x81, SHL V0, V1
+    let rom = [0x60, 0x01, 0x61, 0x81, 0x80, 0x1E];
+    let chip8 = run(quirks(|_| {}), &rom, 3);
+    assert_eq!((chip8.v[0], chip8.v[0xF]), (0x02, 0));
+    let chip8 = run(quirks(|quirks| quirks.shift_uses_vy = true), &rom, 3);
+    assert_eq!((chip8.v[0], chip8.v[0xF]), (0x02, 1));
+}
+
+#[test]
+fn load_store_increment_follows_quirk() {
+    // LD I, 0x300; V0..V2 = 7, 8, 9; LD [I], V2; LD V2, [I]
+    let rom = [0xA3, 0x00, 0x60, 0x07, 0x61, 0x08, 0x62, 0x09, 0xF2, 0x55, 0xF2, 0x65];
+    let chip8 = run(quirks(|_| {}), &rom, 5);
+    assert_eq!((&chip8.memory[0x300..0x303], chip8.i), (&[7, 8, 9][..], 0x300));
+    let chip8 = run(quirks(|_| {}), &rom, 6);
+    assert_eq!((chip8.v[..3].to_vec(), chip8.i), (vec![7, 8, 9], 0x300));
+
+    let chip8 = run(quirks(|quirks| quirks.load_store_increments_i = true), &rom, 5);
+    assert_eq!((&chip8.memory[0x300..0x303], chip8.i), (&[7, 8, 9][..], 0x303));
+    let chip8 = run(quirks(|quirks| quirks.load_store_increments_i = true), &rom, 6);
+    assert_eq!((chip8.v[..3].to_vec(), chip8.i), (vec![0, 0, 0], 0x306));
+}
+
+#[test]
+fn jump_offset_register_follows_quirk() {
+    // V0 = 1, V2 = 4, JP V0, 0x210
+    let rom = [0x60, 0x01, 0x62, 0x04, 0xB2, 0x10];
+    assert_eq!(run(quirks(|_| {}), &rom, 3).pc, 0x211);
+    assert_eq!(run(quirks(|quirks| quirks.jump_uses_vx = true), &rom, 3).pc, 0x214);
+}
+
+#[test]
+fn logic_ops_reset_vf_with_quirk() {
+    for (op, expected) in [(0x11, 0x07), (0x12, 0x01), (0x13, 0x06)] {
+        // VF = 5, V0 = 3, V1 = 5, OR/AND/XOR V0, V1
+        let rom = [0x6F, 0x05, 0x60, 0x03, 0x61, 0x05, 0x80, op];
+        let chip8 = run(quirks(|_| {}), &rom, 4);
+        assert_eq!((chip8.v[0], chip8.v[0xF]), (expected, 5));
+        let chip8 = run(quirks(|quirks| quirks.vf_reset = true), &rom, 4);
+        assert_eq!((chip8.v[0], chip8.v[0xF]), (expected, 0));
+    }
+}
+
+#[test]
+fn sprites_wrap_or_clip_at_the_edges() {
+    // LD I, 0x208; V0 = 60; V1 = 31; DRW V0, V1, 2; two rows of 0xFF
+    let rom = [0xA2, 0x08, 0x60, 0x3C, 0x61, 0x1F, 0xD0, 0x12, 0xFF, 0xFF];
+    let chip8 = run(quirks(|_| {}), &rom, 4);
+    assert_eq!(chip8.display.iter().filter(|&&pixel| pixel != 0).count(), 16);
+    assert!(lit(&chip8, 63, 31) && lit(&chip8, 0, 31) && lit(&chip8, 63, 0) && lit(&chip8, 3, 0));
+
+    let chip8 = run(quirks(|quirks| quirks.clip_sprites = true), &rom, 4);
+    assert_eq!(chip8.display.iter().filter(|&&pixel| pixel != 0).count(), 4);
+    assert!(lit(&chip8, 60, 31) && lit(&chip8, 63, 31));
+    assert!(!lit(&chip8, 0, 31) && !lit(&chip8, 60, 0));
+}
+
+#[test]
+fn display_wait_holds_draws_until_vblank() {
+    // DRW V0, V0, 1 with I pointing at the font's "0"
+    let rom = [0xD0, 0x01];
+    let mut chip8 = run(quirks(|quirks| quirks.display_wait = true), &rom, 1);
+    assert!(chip8.waiting_for_vblank());
+    assert_eq!(chip8.pc, 0x200);
+    assert!(!lit(&chip8, 0, 0));
+
+    chip8.tick_timers();
+    chip8.emulate_cycle().unwrap();
+    assert_eq!(chip8.pc, 0x202);
+    assert!(lit(&chip8, 0, 0));
+    assert!(!chip8.vblank);
+
+    let chip8 = run(quirks(|_| {}), &rom, 1);
+    assert_eq!(chip8.pc, 0x202);
+    assert!(lit(&chip8, 0, 0));
+}
+
+#[test]
+fn quirk_bits_round_trip() {
+    for bits in 0..0x80 {
+        assert_eq!(Quirks::from_bits(bits).to_bits(), bits);
+    }
+    for preset in QuirksPreset::ALL {
+        assert_eq!(Quirks::from_bits(preset.quirks().to_bits()), preset.quirks());
+    }
+}
+
+#[test]
+fn scroll_opcodes_move_the_display() {
+    // SCD 2; SCR; SCL; SCU 2
+    let mut chip8 = run(quirks(|_| {}), &[0x00, 0xC2, 0x00, 0xFB, 0x00, 0xFC, 0x00, 0xD2], 0);
+    chip8.display[3 * 64 + 5] = 1;
+    chip8.emulate_cycle().unwrap();
+    assert!(lit(&chip8, 5, 5) && !lit(&chip8, 5, 3));
+    chip8.emulate_cycle().unwrap();
+    assert!(lit(&chip8, 9, 5) && !lit(&chip8, 5, 5));
+    chip8.emulate_cycle().unwrap();
+    assert!(lit(&chip8, 5, 5) && !lit(&chip8, 9, 5));
+    chip8.emulate_cycle().unwrap();
+    assert!(lit(&chip8, 5, 3));
+    assert_eq!(chip8.display.iter().filter(|&&pixel| pixel != 0).count(), 1);
+
+    // Pixels scrolled off the edge are lost.
+    let mut chip8 = run(quirks(|_| {}), &[0x00, 0xFC], 0);
+    chip8.display[0] = 1;
+    chip8.emulate_cycle().unwrap();
+    assert!(chip8.display.iter().all(|&pixel| pixel == 0));
+}
+
+#[test]
+fn resolution_toggle_resizes_and_clears() {
+    // HIGH; LOW
+    let mut chip8 = run(quirks(|_| {}), &[0x00, 0xFF, 0x00, 0xFE], 0);
+    chip8.display[0] = 1;
+    chip8.emulate_cycle().unwrap();
+    assert!(chip8.hires);
+    assert_eq!((chip8.display_width(), chip8.display_height(), chip8.display.len()), (128, 64, 128 * 64));
+    assert!(chip8.display.iter().all(|&pixel| pixel == 0));
+
+    chip8.display[0] = 1;
+    chip8.emulate_cycle().unwrap();
+    assert!(!chip8.hires);
+    assert_eq!(chip8.display.len(), 64 * 32);
+    assert!(chip8.display.iter().all(|&pixel| pixel == 0));
+}
+
+#[test]
+fn exit_halts_the_interpreter() {
+    let mut chip8 = run(quirks(|_| {}), &[0x00, 0xFD, 0x60, 0x01], 1);
+    assert!(chip8.halted);
+    chip8.emulate_cycle().unwrap();
+    assert_eq!((chip8.pc, chip8.v[0]), (0x200, 0));
+}
+
+// HIGH; LD I, 0x20A; DRW V0, V0, 0 twice; JP 0x208; 16x16 solid sprite
+fn big_sprite_rom(hires: bool) -> Vec<u8> {
+    let mode = if hires { 0xFF } else { 0xE0 };
+    let mut rom = vec![0x00, mode, 0xA2, 0x0A, 0xD0, 0x00, 0xD0, 0x00, 0x12, 0x08];
+    rom.extend([0xFF; 32]);
+    rom
+}
+
+#[test]
+fn big_sprites_draw_16x16_and_collide() {
+    let mut chip8 = run(quirks(|_| {}), &big_sprite_rom(true), 3);
+    assert_eq!(chip8.display.iter().filter(|&&pixel| pixel != 0).count(), 256);
+    assert!(lit(&chip8, 15, 15) && !lit(&chip8, 16, 0) && !lit(&chip8, 0, 16));
+    assert_eq!(chip8.v[0xF], 0);
+
+    chip8.emulate_cycle().unwrap();
+    assert!(chip8.display.iter().all(|&pixel| pixel == 0));
+    assert_eq!(chip8.v[0xF], 1);
+}
+
+#[test]
+fn lores_big_sprites_depend_on_preset() {
+    for preset in QuirksPreset::ALL {
+        let mut chip8 = run(preset.quirks(), &big_sprite_rom(false), 2);
+        chip8.tick_timers();
+        chip8.emulate_cycle().unwrap();
+        assert_eq!(chip8.pc, 0x206, "{}", preset.name());
+        let lit_pixels = chip8.display.iter().filter(|&&pixel| pixel != 0).count();
+        let expected = if preset.quirks().lores_big_sprites { 256 } else { 0 };
+        assert_eq!(lit_pixels, expected, "{}", preset.name());
+    }
+    assert!(QuirksPreset::SuperChip.quirks().lores_big_sprites);
+    assert!(!QuirksPreset::Chip48.quirks().lores_big_sprites);
+    assert!(!QuirksPreset::CosmacVip.quirks().lores_big_sprites);
+}
+
+#[test]
+fn big_font_and_flag_registers() {
+    // V0 = 7; LD HF, V0
+    let chip8 = run(quirks(|_| {}), &[0x60, 0x07, 0xF0, 0x30], 2);
+    let start = BIG_FONTSET_START + 70;
+    assert_eq!(chip8.i as usize, start);
+    assert_eq!(chip8.memory[start..start + 10], SCHIP_BIG_FONTSET[70..80]);
+
+    // V0..V2 = 1, 2, 3; LD R, V2; clear V0..V2; LD V2, R
+    let rom = [0x60, 0x01, 0x61, 0x02, 0x62, 0x03, 0xF2, 0x75, 0x60, 0x00, 0x61, 0x00, 0x62, 0x00, 0xF2, 0x85];
+    let chip8 = run(quirks(|_| {}), &rom, 7);
+    assert_eq!((chip8.rpl_flags[..4].to_vec(), chip8.v[..3].to_vec()), (vec![1, 2, 3, 0], vec![0, 0, 0]));
+    let chip8 = run(quirks(|_| {}), &rom, 8);
+    assert_eq!(chip8.v[..3], [1, 2, 3]);
+}
+
+#[test]
+fn presets_are_distinguishable() {
+    for preset in QuirksPreset::ALL {
+        assert_eq!(preset.quirks().describe(), preset.name());
+    }
+}
+
+fn fault(rom: &[u8], cycles: usize) -> Chip8Error {
+    let mut chip8 = run(quirks(|_| {}), rom, cycles);
+    let pc = chip8.pc;
+    let err = chip8.emulate_cycle().unwrap_err();
+    assert_eq!(chip8.pc, pc);
+    err
+}
+
+#[test]
+fn stack_overflow_and_underflow() {
+    // CALL 0x200 recursing forever
+    let err = fault(&[0x22, 0x00], 16);
+    assert!(matches!(err, Chip8Error::StackOverflow { address: 0x200 }), "{err}");
+    let err = fault(&[0x00, 0xEE], 0);
+    assert!(matches!(err, Chip8Error::StackUnderflow { address: 0x200 }), "{err}");
+}
+
+#[test]
+fn index_past_the_end_of_memory() {
+    // LD I, 0xFFF followed by a two-byte store, load or sprite
+    for opcode in [[0xF1, 0x55], [0xF1, 0x65], [0xD0, 0x02]] {
+        let err = fault(&[0xAF, 0xFF, opcode[0], opcode[1]], 1);
+        assert!(matches!(err, Chip8Error::MemoryOutOfBounds { address: 0x1000, pc: 0x202 }), "{err}");
+    }
+}
+
+#[test]
+fn key_index_out_of_range() {
+    // V0 = 0x10; SKP V0 / SKNP V0
+    for opcode in [0x9E, 0xA1] {
+        let err = fault(&[0x60, 0x10, 0xE0, opcode], 1);
+        assert!(matches!(err, Chip8Error::InvalidKey { key: 0x10, address: 0x202 }), "{err}");
+    }
+}
+
+#[test]
+fn rom_must_fit_in_memory() {
+    for preset in QuirksPreset::ALL {
+        let capacity = preset.memory_size() - PROGRAM_START;
+        let mut chip8 = Chip8::with_memory_size(preset.memory_size());
+        assert!(chip8.load_rom_bytes(&vec![0; capacity]).is_ok());
+        let err = chip8.load_rom_bytes(&vec![0; capacity + 1]).unwrap_err();
+        assert!(
+            matches!(err, Chip8Error::RomTooLarge { size, capacity: reported } if size == capacity + 1 && reported == capacity),
+            "{}: {err}",
+            preset.name()
+        );
+    }
+}
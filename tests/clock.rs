@@ -0,0 +1,57 @@
+use std::time::Duration;
+
+use chip8_emulator::clock::{Clock, TIMER_FREQUENCY};
+
+fn drain(clock: &mut Clock) -> Vec<usize> {
+    std::iter::from_fn(|| clock.next_frame()).collect()
+}
+
+#[test]
+fn instructions_split_evenly_across_frames() {
+    let mut clock = Clock::new(700);
+    let frames: Vec<usize> = (0..TIMER_FREQUENCY).map(|_| clock.virtual_frame()).collect();
+    assert_eq!(frames[..6], [11, 12, 12, 11, 12, 12]);
+    assert_eq!(frames.iter().sum::<usize>(), 700);
+
+    let mut clock = Clock::new(600);
+    assert!((0..TIMER_FREQUENCY).all(|_| clock.virtual_frame() == 10));
+}
+
+#[test]
+fn partial_frames_carry_over() {
+    let half_frame = Duration::from_nanos(8_333_334);
+    let mut clock = Clock::new(700);
+    clock.accumulate(half_frame);
+    assert_eq!(clock.next_frame(), None);
+    clock.accumulate(half_frame);
+    assert_eq!(drain(&mut clock), [11]);
+
+    clock.accumulate(half_frame * 5);
+    assert_eq!(drain(&mut clock), [12, 12]);
+    clock.accumulate(half_frame);
+    assert_eq!(drain(&mut clock), [11]);
+}
+
+#[test]
+fn pending_frames_are_capped() {
+    let mut clock = Clock::new(700);
+    clock.accumulate(Duration::from_secs(1));
+    let frames = drain(&mut clock);
+    assert_eq!(frames.len(), 10);
+    // The dropped frames are skipped, not replayed: the last ten frames of the second remain.
+    assert_eq!(frames.iter().sum::<usize>(), 700 - 50 * 700 / 60);
+
+    clock.accumulate(Duration::from_nanos(16_666_667));
+    assert_eq!(drain(&mut clock), [11]);
+}
+
+#[test]
+fn virtual_frames_continue_from_real_time() {
+    let mut clock = Clock::new(700);
+    clock.accumulate(Duration::from_secs(5));
+    assert_eq!(drain(&mut clock).len(), 10);
+    let frames: Vec<usize> = (0..TIMER_FREQUENCY).map(|_| clock.virtual_frame()).collect();
+    assert_eq!(frames[..3], [11, 12, 12]);
+    assert_eq!(frames.iter().sum::<usize>(), 700);
+    assert_eq!(clock.next_frame(), None);
+}
@@ -0,0 +1,120 @@
+use chip8_emulator::chip8::Chip8;
+use std::path::Path;
+
+use chip8_emulator::coverage::{self, BranchCoverage, Coverage};
+use chip8_emulator::symbols::SymbolTable;
+
+// LD V0, 1; SE V0, 1; JP 0x20A; LD I, 0x20E; LD V0, [I]; JP 0x20A; JP 0x20A; data AB CD
+const ROM: [u8; 16] = [
+    0x60, 0x01, 0x30, 0x01, 0x12, 0x0A, 0xA2, 0x0E, 0xF0, 0x65, 0x12, 0x0A, 0x12, 0x0A, 0xAB, 0xCD,
+];
+
+fn covered(cycles: usize) -> Coverage {
+    let mut chip8 = Chip8::new();
+    chip8.load_rom_bytes(&ROM).unwrap();
+    chip8.coverage = Some(Coverage::new());
+    for _ in 0..cycles {
+        chip8.emulate_cycle().unwrap();
+    }
+    chip8.coverage.unwrap()
+}
+
+fn output(write: impl FnOnce(&mut Vec<u8>) -> std::io::Result<()>) -> String {
+    let mut out = Vec::new();
+    write(&mut out).unwrap();
+    String::from_utf8(out).unwrap()
+}
+
+#[test]
+fn marks_executions_reads_and_branches() {
+    let coverage = covered(6);
+    assert_eq!(coverage.executions(0x200), 1);
+    assert_eq!(coverage.executions(0x204), 0);
+    assert_eq!(coverage.executions(0x20A), 2);
+    assert_eq!(coverage.executed_addresses().collect::<Vec<_>>(), [0x200, 0x202, 0x206, 0x208, 0x20A]);
+
+    assert!(coverage.was_read(0x20E));
+    assert!(!coverage.was_read(0x20F));
+    assert_eq!(coverage.branch(0x202), Some(BranchCoverage { not_skipped: 0, skipped: 1 }));
+    assert_eq!(coverage.branch(0x200), None);
+}
+
+#[test]
+fn writes_annotated_listing() {
+    let coverage = covered(6);
+    assert_eq!(
+        output(|out| coverage.write_listing(out, &ROM, &SymbolTable::new())),
+        "\
+0200            1|     LD   V0, 0x01
+0202           1*|     SE   V0, 0x01
+0204        #####|     JP   loop_20A
+0206            1|     LD   I, data_20E
+0208            1|     LD   V0, [I]
+                 | loop_20A:
+020A            2|     JP   loop_20A
+020C             |     db   0x12, 0x0A
+                 | data_20E:
+020E     read 1/2|     db   0xAB, 0xCD
+
+; 5/6 instructions executed
+; 1/4 data bytes read
+; 0/1 skips taken both ways
+; ##### never executed, * skip only ever went one way
+"
+    );
+}
+
+#[test]
+fn lcov_lines_refer_to_the_listing() {
+    let coverage = covered(6);
+    assert_eq!(
+        output(|out| coverage.write_lcov(out, &ROM, &SymbolTable::new(), "game.lst")),
+        "\
+TN:
+SF:game.lst
+FNF:0
+FNH:0
+BRDA:2,0,0,0
+BRDA:2,0,1,1
+BRF:2
+BRH:1
+DA:1,1
+DA:2,1
+DA:3,0
+DA:4,1
+DA:5,1
+DA:7,2
+LF:6
+LH:5
+end_of_record
+"
+    );
+}
+
+#[test]
+fn lcov_lists_named_code_as_functions() {
+    let coverage = covered(6);
+    let mut symbols = SymbolTable::new();
+    symbols.insert(0x20A, "spin");
+    symbols.insert(0x20E, "sprite");
+    let lcov = output(|out| coverage.write_lcov(out, &ROM, &symbols, "game.lst"));
+    assert!(lcov.contains("FN:6,spin\nFNDA:2,spin\nFNF:1\nFNH:1\n"), "{lcov}");
+    assert!(!lcov.contains("sprite"));
+
+    let unexecuted = Coverage::new();
+    let lcov = output(|out| unexecuted.write_lcov(out, &ROM, &symbols, "game.lst"));
+    assert!(lcov.contains("BRDA:2,0,0,-\nBRDA:2,0,1,-\nBRF:2\nBRH:0\n"), "{lcov}");
+    assert!(lcov.contains("FNH:0\n") && lcov.contains("LH:0\n"));
+}
+
+#[test]
+fn lcov_path_never_replaces_the_listing() {
+    for (listing, lcov) in [
+        ("out/game.lst", "out/game.lcov.info"),
+        ("out/game.info", "out/game.lcov.info"),
+        ("out/game.lcov.info", "out/game.lcov.lcov.info"),
+        ("game", "game.lcov.info"),
+    ] {
+        assert_eq!(coverage::lcov_path(Path::new(listing)), Path::new(lcov));
+    }
+}
@@ -0,0 +1,92 @@
+use chip8_emulator::chip8::Chip8;
+use chip8_emulator::expr::{BinaryOp, Expr, Register};
+
+fn eval(text: &str, chip8: &Chip8) -> i64 {
+    Expr::parse(text).unwrap_or_else(|err| panic!("`{text}`: {err}")).eval(chip8)
+}
+
+#[test]
+fn binary_operators_follow_precedence() {
+    let chip8 = Chip8::new();
+    for (text, expected) in [
+        ("1 + 2 & 3", 3),
+        ("1 | 2 ^ 3", 1),
+        ("6 & 3 | 8", 10),
+        ("1 + 1 == 2", 1),
+        ("2 == 2 && 3 > 4 || 1", 1),
+        ("0 || 1 && 0", 0),
+        ("(1 + 2) & 4", 0),
+        ("10 - 2 - 3", 5),
+        ("0x10 + 010", 26),
+    ] {
+        assert_eq!(eval(text, &chip8), expected, "{text}");
+    }
+
+    assert_eq!(
+        Expr::parse("1 - 2 + 3").unwrap(),
+        Expr::Binary(
+            BinaryOp::Add,
+            Box::new(Expr::Binary(BinaryOp::Sub, Box::new(Expr::Number(1)), Box::new(Expr::Number(2)))),
+            Box::new(Expr::Number(3)),
+        )
+    );
+}
+
+#[test]
+fn unary_operators() {
+    let chip8 = Chip8::new();
+    for (text, expected) in [("!0", 1), ("!5", 0), ("!!7", 1), ("-3", -3), ("--3", 3), ("-1 + 4", 3), ("!0 + 1", 2)] {
+        assert_eq!(eval(text, &chip8), expected, "{text}");
+    }
+}
+
+#[test]
+fn registers_and_memory() {
+    let mut chip8 = Chip8::new();
+    chip8.v[0x3] = 0x10;
+    chip8.v[0xF] = 1;
+    chip8.i = 0x300;
+    chip8.pc = 0x246;
+    chip8.sp = 2;
+    chip8.delay_timer = 9;
+    chip8.sound_timer = 4;
+    chip8.memory[0x300] = 0xAB;
+    chip8.memory[0x301] = 0xCD;
+
+    assert_eq!(Expr::parse("vf").unwrap(), Expr::Register(Register::V(0xF)));
+    for (text, expected) in [
+        ("V3", 0x10),
+        ("vf + V0", 1),
+        ("I", 0x300),
+        ("pc", 0x246),
+        ("SP + DT + ST", 15),
+        ("[0x300]", 0xAB),
+        ("[I + 1]", 0xCD),
+        ("[I + VF] - [I]", 0x22),
+        ("[[0x300] + 0x255]", 0xAB),
+        ("[0x10000]", 0),
+        ("[-1]", 0),
+        ("V3 == 0x10 && I > 0x2FF", 1),
+    ] {
+        assert_eq!(eval(text, &chip8), expected, "{text}");
+    }
+}
+
+#[test]
+fn parse_errors_report_column() {
+    for (text, column, message) in [
+        ("V3 = 1", 4, "use `==` for comparison"),
+        ("V3 == ", 7, "unexpected end of expression"),
+        ("VG > 1", 1, "unknown register 'VG'"),
+        ("V10", 1, "unknown register 'V10'"),
+        ("(1 + 2", 7, "expected `)`"),
+        ("[I", 3, "expected `]`"),
+        ("1 2", 3, "unexpected trailing input"),
+        ("1 + $", 5, "unexpected character '$'"),
+        ("0xZZ", 1, "invalid number '0xZZ'"),
+        ("1 + )", 5, "unexpected `)`"),
+    ] {
+        let err = Expr::parse(text).unwrap_err();
+        assert_eq!((err.column, err.message.as_str()), (column, message), "{text}");
+    }
+}
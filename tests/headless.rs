@@ -0,0 +1,110 @@
+use chip8_emulator::chip8::{Chip8, DISPLAY_HEIGHT, DISPLAY_PALETTE, DISPLAY_WIDTH};
+use chip8_emulator::headless::{self, HeadlessRunner, KeyScript};
+
+#[test]
+fn key_script_applies_events_by_frame() {
+    let script = KeyScript::parse(
+        "# hold 5 for two frames\n\
+         2 5 up\n\
+         0 5 down   # comment\n\
+         \n\
+         1 0xA down\n",
+    )
+    .unwrap();
+    let mut chip8 = Chip8::new();
+
+    script.apply(0, &mut chip8);
+    assert_eq!(chip8.keypad[0x5], 1);
+    assert_eq!(chip8.keypad[0xA], 0);
+    script.apply(1, &mut chip8);
+    assert_eq!(chip8.keypad[0xA], 1);
+    script.apply(2, &mut chip8);
+    assert_eq!(chip8.keypad[0x5], 0);
+    assert_eq!(chip8.keypad[0xA], 1);
+}
+
+#[test]
+fn key_script_errors_report_line() {
+    for (text, line, message) in [
+        ("0 1 down\n1 2", 2, "expected `<frame> <key> <down|up>`"),
+        ("x 1 down", 1, "invalid frame number"),
+        ("\n\n3 G down", 3, "key must be a hex digit 0-F"),
+        ("0 10 down", 1, "key must be a hex digit 0-F"),
+        ("0 1 press", 1, "action must be `down` or `up`"),
+    ] {
+        let err = KeyScript::parse(text).unwrap_err();
+        assert_eq!((err.line, err.message.as_str()), (line, message), "{text:?}");
+    }
+}
+
+#[test]
+fn state_json_lists_registers_and_display() {
+    let mut chip8 = Chip8::new();
+    chip8.v[0] = 7;
+    chip8.v[0xF] = 1;
+    chip8.i = 0x300;
+    chip8.stack[0] = 0x204;
+    chip8.sp = 1;
+    chip8.delay_timer = 3;
+    chip8.display[0] = 1;
+    chip8.display[DISPLAY_WIDTH + 1] = 3;
+
+    let json = headless::state_json(&chip8, 42);
+    let rows: Vec<&str> = json.lines().filter(|line| line.starts_with("    \"")).collect();
+    let header: Vec<&str> = json.lines().take(13).collect();
+
+    assert_eq!(
+        header,
+        [
+            "{",
+            "  \"frame\": 42,",
+            "  \"pc\": 512,",
+            "  \"i\": 768,",
+            "  \"sp\": 1,",
+            "  \"v\": [7, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 1],",
+            "  \"stack\": [516, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0],",
+            "  \"delay_timer\": 3,",
+            "  \"sound_timer\": 0,",
+            "  \"hires\": false,",
+            "  \"halted\": false,",
+            "  \"display\": [",
+            &format!("    \"1{}\",", "0".repeat(DISPLAY_WIDTH - 1)),
+        ]
+    );
+    assert_eq!(rows.len(), DISPLAY_HEIGHT);
+    assert!(rows[1].starts_with("    \"03000"));
+    assert!(json.ends_with("\"\n  ]\n}\n"));
+}
+
+#[test]
+fn png_has_display_size_and_palette() {
+    let mut chip8 = Chip8::new();
+    chip8.display[0] = 1;
+    chip8.display[DISPLAY_WIDTH * DISPLAY_HEIGHT - 1] = 2;
+    let mut bytes = Vec::new();
+    headless::write_png(&chip8, &mut bytes).unwrap();
+
+    let mut reader = png::Decoder::new(std::io::Cursor::new(bytes)).read_info().unwrap();
+    let info = reader.info();
+    assert_eq!((info.width, info.height), (DISPLAY_WIDTH as u32, DISPLAY_HEIGHT as u32));
+    assert_eq!(info.color_type, png::ColorType::Indexed);
+    assert_eq!(info.palette.as_deref(), Some(&DISPLAY_PALETTE.concat()[..]));
+
+    let mut pixels = vec![0; reader.output_buffer_size().unwrap()];
+    reader.next_frame(&mut pixels).unwrap();
+    assert_eq!(pixels, chip8.display);
+}
+
+#[test]
+fn seeded_runs_are_reproducible() {
+    let run = |seed| {
+        let mut chip8 = Chip8::new();
+        chip8.load_rom_bytes(&[0xC0, 0xFF, 0xC1, 0xFF, 0xC2, 0xFF, 0x12, 0x06]).unwrap();
+        chip8.seed_rng(seed);
+        let mut runner = HeadlessRunner::new(chip8);
+        runner.run_frame().unwrap();
+        runner.chip8.v
+    };
+    assert_eq!(run(1), run(1));
+    assert_ne!(run(1), run(2));
+}
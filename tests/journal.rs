@@ -0,0 +1,115 @@
+use chip8_emulator::chip8::Chip8;
+use chip8_emulator::journal::DEFAULT_JOURNAL_CAPACITY;
+
+const PROGRAM: [u16; 15] = [
+    0x00E0, // CLS
+    0x6005, // LD   V0, 0x05
+    0x610A, // LD   V1, 0x0A
+    0xF029, // LD   F, V0
+    0xD015, // DRW  V0, V1, 5
+    0xA300, // LD   I, 0x300
+    0xF233, // LD   B, V2
+    0xF155, // LD   [I], V1
+    0x7001, // ADD  V0, 0x01
+    0x8014, // ADD  V0, V1
+    0x00E0, // CLS
+    0xD015, // DRW  V0, V1, 5
+    0x221C, // CALL 0x21C
+    0x121A, // JP   0x21A
+    0x00EE, // RET
+];
+
+#[test]
+fn step_back_undoes_registers_memory_and_display() {
+    let rom: Vec<u8> = PROGRAM.iter().flat_map(|opcode| opcode.to_be_bytes()).collect();
+    let mut chip8 = Chip8::new();
+    chip8.journal.set_capacity(DEFAULT_JOURNAL_CAPACITY);
+    chip8.load_rom_bytes(&rom).unwrap();
+
+    let mut states = Vec::new();
+    for _ in 0..14 {
+        states.push(chip8.save_state());
+        chip8.emulate_cycle().unwrap();
+    }
+    assert_eq!(chip8.pc, 0x21A);
+    assert_ne!(chip8.memory[0x300..0x305], [0; 5]);
+    assert!(chip8.display.iter().any(|&pixel| pixel != 0));
+
+    while let Some(expected) = states.pop() {
+        assert!(chip8.step_back(), "{} steps left", states.len());
+        assert_eq!(chip8.save_state(), expected, "after undoing step {}", states.len());
+    }
+    assert!(!chip8.step_back());
+}
+
+#[test]
+fn load_state_keeps_journal_capacity() {
+    let mut chip8 = Chip8::new();
+    chip8.journal.set_capacity(DEFAULT_JOURNAL_CAPACITY);
+    chip8.load_rom_bytes(&[0x60, 0x01, 0x12, 0x00]).unwrap();
+    let state = chip8.save_state();
+    chip8.emulate_cycle().unwrap();
+
+    chip8.load_state(&state).unwrap();
+    assert!(chip8.journal.is_empty());
+    assert_eq!(chip8.journal.capacity(), DEFAULT_JOURNAL_CAPACITY);
+    chip8.emulate_cycle().unwrap();
+    assert!(chip8.step_back());
+    assert_eq!(chip8.save_state(), state);
+}
+
+fn counter(capacity: usize) -> Chip8 {
+    // ADD V0, 0x01; JP 0x200
+    let mut chip8 = Chip8::new();
+    chip8.journal.set_capacity(capacity);
+    chip8.load_rom_bytes(&[0x70, 0x01, 0x12, 0x00]).unwrap();
+    chip8
+}
+
+fn run_saving(chip8: &mut Chip8, cycles: usize) -> Vec<Vec<u8>> {
+    let mut states = Vec::new();
+    for _ in 0..cycles {
+        states.push(chip8.save_state());
+        chip8.emulate_cycle().unwrap();
+    }
+    states
+}
+
+#[test]
+fn full_journal_evicts_oldest_entries() {
+    for capacity in [1, 3, DEFAULT_JOURNAL_CAPACITY] {
+        let mut chip8 = counter(capacity);
+        let states = run_saving(&mut chip8, capacity + 5);
+        assert_eq!(chip8.journal.len(), capacity);
+
+        let mut undone = 0;
+        while chip8.step_back() {
+            undone += 1;
+        }
+        assert_eq!(undone, capacity);
+        assert_eq!(chip8.save_state(), states[5]);
+    }
+}
+
+#[test]
+fn shrinking_capacity_drops_oldest_entries() {
+    let mut chip8 = counter(10);
+    let states = run_saving(&mut chip8, 8);
+    chip8.journal.set_capacity(2);
+    assert_eq!(chip8.journal.len(), 2);
+    assert!(chip8.step_back() && chip8.step_back());
+    assert!(!chip8.step_back());
+    assert_eq!(chip8.save_state(), states[6]);
+}
+
+#[test]
+fn zero_capacity_disables_recording() {
+    let mut chip8 = counter(0);
+    assert!(!chip8.journal.is_enabled());
+    run_saving(&mut chip8, 4);
+    assert!(chip8.journal.is_empty());
+    assert!(!chip8.step_back());
+    assert_eq!(chip8.v[0], 2);
+
+    assert_eq!(Chip8::new().journal.capacity(), 0);
+}
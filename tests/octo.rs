@@ -0,0 +1,147 @@
+use chip8_emulator::octo;
+
+fn words(source: &str) -> Vec<u16> {
+    let program = octo::compile(source).unwrap_or_else(|err| panic!("{err}\n{source}"));
+    program.rom.chunks(2).map(|pair| u16::from_be_bytes([pair[0], pair.get(1).copied().unwrap_or(0)])).collect()
+}
+
+fn error(source: &str) -> (usize, String) {
+    let err = octo::compile(source).expect_err(source);
+    (err.line, err.message)
+}
+
+#[test]
+fn comparisons_lower_through_vf() {
+    for (condition, expected) in [
+        ("v1 == 5", &[0x4105][..]),
+        ("v1 != 5", &[0x3105]),
+        ("v1 == v2", &[0x9120]),
+        ("v1 != v2", &[0x5120]),
+        ("v1 key", &[0xE1A1]),
+        ("v1 -key", &[0xE19E]),
+        ("v1 < 5", &[0x6F05, 0x8F17, 0x4F00]),
+        ("v1 >= 5", &[0x6F05, 0x8F17, 0x3F00]),
+        ("v1 > 5", &[0x6F05, 0x8F15, 0x4F00]),
+        ("v1 <= 5", &[0x6F05, 0x8F15, 0x3F00]),
+        ("v1 < v2", &[0x8F10, 0x8F25, 0x4F00]),
+        ("v1 >= v2", &[0x8F10, 0x8F25, 0x3F00]),
+        ("v1 > v2", &[0x8F20, 0x8F15, 0x4F00]),
+        ("v1 <= v2", &[0x8F20, 0x8F15, 0x3F00]),
+    ] {
+        let mut expected = expected.to_vec();
+        expected.push(0x6301);
+        assert_eq!(words(&format!("if {condition} then v3 := 1")), expected, "{condition}");
+    }
+}
+
+#[test]
+fn if_begin_else_end() {
+    assert_eq!(words("if v0 == 1 begin v1 := 2 end"), [0x3001, 0x1206, 0x6102]);
+    assert_eq!(
+        words("if v0 == 1 begin v1 := 2 else v1 := 3 end v2 := 4"),
+        [0x3001, 0x1208, 0x6102, 0x120A, 0x6103, 0x6204]
+    );
+    assert_eq!(
+        words("if v0 < 3 begin v1 := 2 end"),
+        [0x6F03, 0x8F07, 0x3F00, 0x120A, 0x6102]
+    );
+}
+
+#[test]
+fn loop_while_again() {
+    assert_eq!(
+        words("loop v0 += 1 while v0 != 10 v1 += 2 again"),
+        [0x7001, 0x400A, 0x120A, 0x7102, 0x1200]
+    );
+    assert_eq!(
+        words("v2 := 0 loop while v2 < 4 v2 += 1 again"),
+        [0x6200, 0x6F04, 0x8F27, 0x3F00, 0x120E, 0x7201, 0x1202]
+    );
+}
+
+#[test]
+fn macros_substitute_arguments() {
+    let source = "
+        :macro add-twice reg amount { reg += amount reg += amount }
+        :macro clear-all { clear v0 := 0 }
+        add-twice v3 4
+        clear-all
+        add-twice v5 0x10
+    ";
+    assert_eq!(words(source), [0x7304, 0x7304, 0x00E0, 0x6000, 0x7510, 0x7510]);
+}
+
+#[test]
+fn calc_constants() {
+    let source = "
+        :calc width { 8 * 4 }
+        :calc half { width / 2 }
+        :calc grouped { 2 * 3 + 1 }
+        :calc negative { -3 / 2 }
+        v0 := width
+        v1 := half
+        v2 := grouped
+        v3 := negative
+        :calc low { HERE & 0xFF }
+        v4 := low
+        :byte { low + 1 }
+    ";
+    let program = octo::compile(source).unwrap();
+    assert_eq!(program.rom, [0x60, 0x20, 0x61, 0x10, 0x62, 0x08, 0x63, 0xFE, 0x64, 0x08, 0x09]);
+}
+
+#[test]
+fn unpack_splits_an_address() {
+    assert_eq!(
+        words(":unpack 0xA data i := data : data 0x12 0x34"),
+        [0x60A2, 0x6106, 0xA206, 0x1234]
+    );
+    assert_eq!(words(": data 0x12 0x34 :unpack 0 data"), [0x1234, 0x6002, 0x6100]);
+}
+
+#[test]
+fn forward_references_are_patched() {
+    let program = octo::compile(
+        ": main
+           jump later
+           i := long later
+           sub
+         : later
+           jump0 sub
+         : sub
+           return",
+    )
+    .unwrap();
+    let opcodes: Vec<u16> = program.rom.chunks(2).map(|pair| u16::from_be_bytes([pair[0], pair[1]])).collect();
+    assert_eq!(opcodes, [0x1208, 0xF000, 0x0208, 0x220A, 0xB20A, 0x00EE]);
+    assert_eq!(program.labels["main"], 0x200);
+    assert_eq!(program.labels["later"], 0x208);
+    assert_eq!(program.labels["sub"], 0x20A);
+
+    assert_eq!(words(": helper return : main helper"), [0x1204, 0x00EE, 0x2202]);
+}
+
+#[test]
+fn undefined_labels_and_macros_are_errors() {
+    assert_eq!(error("v0 := 1\njump nowhere\n"), (2, "undefined label 'nowhere'".to_string()));
+    assert_eq!(
+        error("i := long missing\n: main\njump elsewhere"),
+        (1, "undefined label 'missing'".to_string())
+    );
+    assert_eq!(error("v0 := 1\nfancy-macro\nclear"), (2, "undefined label 'fancy-macro'".to_string()));
+    assert_eq!(error("twice v1\n:macro twice r { r += 1 }"), (2, "unknown operator ':macro' for a register".to_string()));
+    assert_eq!(error(":calc x { y + 1 }"), (1, "undefined name 'y' in :calc".to_string()));
+    assert_eq!(error("v0 := size"), (1, "undefined name 'size'".to_string()));
+}
+
+#[test]
+fn control_flow_above_0xfff_is_an_error() {
+    let beyond = |keyword: &str, target: u16| format!("'{keyword}' target {target:#06X} is beyond the 12-bit address range");
+    assert_eq!(error(":org 0x1234\nloop\nv0 += 1\nagain"), (4, beyond("again", 0x1234)));
+    assert_eq!(error(":org 0xFFC\nloop\nwhile v0 != 1\nv0 += 1\nagain"), (5, beyond("again", 0x1004)));
+    assert_eq!(error(":org 0xFFA\nif v0 == 1 begin\nv1 := 2\nend"), (4, beyond("end", 0x1000)));
+    assert_eq!(error(":org 0xFF8\nif v0 == 1 begin\nv1 := 2\nelse\nv1 := 3\nend"), (4, beyond("else", 0x1000)));
+
+    let program = octo::compile(":org 0xFF8\nloop\nwhile v0 != 1\nagain").unwrap();
+    assert_eq!(program.rom[0xFF8 - 0x200..], [0x40, 0x01, 0x1F, 0xFE, 0x1F, 0xF8]);
+}
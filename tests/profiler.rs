@@ -0,0 +1,86 @@
+use chip8_emulator::chip8::Chip8;
+use chip8_emulator::profiler::{Profiler, SubroutineCycles};
+use chip8_emulator::symbols::SymbolTable;
+
+// main: CALL 0x206; JP 0x202
+// 0x206: LD V0, 1; CALL 0x20C; RET
+// 0x20C: ADD V0, 1; RET
+const NESTED_CALLS: [u8; 16] = [
+    0x22, 0x06, 0x12, 0x02, 0x00, 0xEE, 0x60, 0x01, 0x22, 0x0C, 0x00, 0xEE, 0x70, 0x01, 0x00, 0xEE,
+];
+
+fn profile(cycles: usize) -> Profiler {
+    let mut chip8 = Chip8::new();
+    chip8.load_rom_bytes(&NESTED_CALLS).unwrap();
+    let mut profiler = Profiler::new();
+    for cycle in 0..cycles {
+        profiler.record(&chip8);
+        chip8.emulate_cycle().unwrap();
+        if cycle % 4 == 3 {
+            profiler.end_frame();
+        }
+    }
+    profiler
+}
+
+fn output(write: impl FnOnce(&mut Vec<u8>) -> std::io::Result<()>) -> String {
+    let mut out = Vec::new();
+    write(&mut out).unwrap();
+    String::from_utf8(out).unwrap()
+}
+
+#[test]
+fn counts_addresses_classes_and_frames() {
+    let profiler = profile(10);
+    assert_eq!(profiler.total(), 10);
+
+    let hot = profiler.hot_addresses();
+    assert_eq!(hot[0], (0x202, 0x1202, 4));
+    assert_eq!(
+        hot[1..].iter().map(|&(address, _, count)| (address, count)).collect::<Vec<_>>(),
+        [(0x200, 1), (0x206, 1), (0x208, 1), (0x20A, 1), (0x20C, 1), (0x20E, 1)]
+    );
+    assert_eq!(
+        profiler.opcode_classes(),
+        [("1NNN", 4), ("00EE", 2), ("2NNN", 2), ("6XNN", 1), ("7XNN", 1)]
+    );
+    assert_eq!(profiler.frame_cycles().collect::<Vec<_>>(), [4, 4]);
+    assert_eq!(profiler.percent(4), 40.0);
+}
+
+#[test]
+fn attributes_cycles_to_subroutines() {
+    let profiler = profile(10);
+    let cycles = |address, exclusive, inclusive| SubroutineCycles { address, exclusive, inclusive };
+    assert_eq!(profiler.subroutines(), [cycles(0x200, 5, 10), cycles(0x206, 3, 5), cycles(0x20C, 2, 2)]);
+}
+
+#[test]
+fn writes_folded_stacks() {
+    let profiler = profile(10);
+    assert_eq!(
+        output(|out| profiler.write_folded(out, &SymbolTable::new())),
+        "main 5\nmain;sub_206 3\nmain;sub_206;sub_20C 2\n"
+    );
+
+    let symbols = SymbolTable::parse(r#"{ "labels": { "draw player": 524, "a;b": 518 } }"#).unwrap();
+    assert_eq!(
+        output(|out| profiler.write_folded(out, &symbols)),
+        "main 5\nmain;a_b 3\nmain;a_b;draw_player 2\n"
+    );
+}
+
+#[test]
+fn csv_quotes_text_columns() {
+    let profiler = profile(10);
+    let symbols = SymbolTable::parse(r#"{ "labels": { "draw, \"fast\"": 524 } }"#).unwrap();
+    let csv = output(|out| profiler.write_csv(out, &symbols));
+    let lines: Vec<&str> = csv.lines().collect();
+
+    assert_eq!(lines[0], "address,symbol,opcode,class,instruction,count,percent");
+    assert_eq!(lines[1], "0x0202,\"\",1202,1NNN,\"JP   0x202\",4,40.000");
+    assert_eq!(lines[2], "0x0200,\"\",2206,2NNN,\"CALL 0x206\",1,10.000");
+    assert_eq!(lines[6], "0x020C,\"draw, \"\"fast\"\"\",7001,7XNN,\"ADD  V0, 0x01\",1,10.000");
+    assert_eq!(lines[7], "0x020E,\"draw, \"\"fast\"\"+0x2\",00EE,00EE,\"RET\",1,10.000");
+    assert_eq!(lines.len(), 8);
+}
@@ -0,0 +1,72 @@
+use chip8_emulator::chip8::{Chip8, XO_MEMORY_SIZE};
+use chip8_emulator::rewind::RewindBuffer;
+use rand::rngs::StdRng;
+use rand::{Rng, SeedableRng};
+
+fn mutate(chip8: &mut Chip8, rng: &mut StdRng) {
+    for _ in 0..rng.random_range(0..20) {
+        let address = rng.random_range(0..chip8.memory.len());
+        chip8.memory[address] = rng.random();
+    }
+    chip8.v[rng.random_range(0..16)] = rng.random();
+    chip8.pc = rng.random_range(0x200..0x1000) & !1;
+    let pixel = rng.random_range(0..chip8.display.len());
+    chip8.display[pixel] ^= 1;
+}
+
+#[test]
+fn rewind_restores_every_pushed_state() {
+    let mut rng = StdRng::seed_from_u64(0xC8);
+    let mut buffer = RewindBuffer::default();
+    let mut chip8 = Chip8::new();
+    let mut states = Vec::new();
+
+    for frame in 0..60 {
+        if frame == 30 {
+            let mut xo = Chip8::with_memory_size(XO_MEMORY_SIZE);
+            xo.memory[..chip8.memory.len()].copy_from_slice(&chip8.memory);
+            chip8 = xo;
+        }
+        mutate(&mut chip8, &mut rng);
+        buffer.push(&chip8);
+        states.push(chip8.save_state());
+    }
+    assert_eq!(buffer.len(), 60);
+
+    states.pop();
+    while let Some(expected) = states.pop() {
+        let restored = buffer.rewind().expect("state still buffered");
+        assert_eq!(restored.save_state(), expected, "{} states left", states.len());
+    }
+    assert!(buffer.rewind().is_none());
+    assert_eq!(buffer.len(), 1);
+}
+
+#[test]
+fn eviction_keeps_buffer_under_budget() {
+    let mut rng = StdRng::seed_from_u64(0xC8);
+    let mut chip8 = Chip8::new();
+    chip8.memory.iter_mut().for_each(|byte| *byte = rng.random());
+    let snapshot_size = chip8.save_state().len();
+    let budget = snapshot_size + 2048;
+    let mut buffer = RewindBuffer::new(budget);
+
+    let mut states = Vec::new();
+    for _ in 0..500 {
+        mutate(&mut chip8, &mut rng);
+        buffer.push(&chip8);
+        states.push(chip8.save_state());
+        assert!(buffer.memory_used() <= budget, "{} > {budget}", buffer.memory_used());
+    }
+    assert!(buffer.len() > 1 && buffer.len() < 500, "{} states kept", buffer.len());
+
+    // The newest states survive eviction.
+    let kept = buffer.len();
+    for expected in states.iter().rev().skip(1).take(kept - 1) {
+        assert_eq!(&buffer.rewind().unwrap().save_state(), expected);
+    }
+    assert!(buffer.rewind().is_none());
+
+    buffer.set_budget(0);
+    assert_eq!(buffer.len(), 1);
+}
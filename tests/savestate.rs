@@ -0,0 +1,122 @@
+use chip8_emulator::chip8::{Chip8, XO_MEMORY_SIZE};
+use chip8_emulator::quirks::QuirksPreset;
+use chip8_emulator::savestate::{EMULATOR_VERSION, SaveStateError};
+
+fn sample_state() -> Chip8 {
+    let mut chip8 = Chip8::new();
+    chip8.quirks = QuirksPreset::CosmacVip.quirks();
+    chip8.load_rom_bytes(&[0x60, 0x2A, 0xA3, 0x00, 0x22, 0x0A]).unwrap();
+    chip8.v[0xF] = 1;
+    chip8.i = 0x345;
+    chip8.pc = 0x20A;
+    chip8.stack[0] = 0x204;
+    chip8.sp = 1;
+    chip8.delay_timer = 30;
+    chip8.sound_timer = 4;
+    chip8.audio_pattern = Some([0xAA; 16]);
+    chip8.pitch = 80;
+    chip8.rpl_flags[2] = 9;
+    chip8.keypad[7] = 1;
+    chip8.display[100] = 1;
+    chip8
+}
+
+// Magic, format version and the length byte of the emulator version string.
+const VERSION_OFFSET: usize = 4 + 2 + 1;
+
+#[test]
+fn save_state_round_trips() {
+    let chip8 = sample_state();
+    let data = chip8.save_state();
+    let restored = Chip8::from_state(&data).unwrap();
+
+    assert_eq!(restored.quirks, chip8.quirks);
+    assert_eq!((restored.pc, restored.i, restored.sp), (0x20A, 0x345, 1));
+    assert_eq!(restored.v, chip8.v);
+    assert_eq!(restored.stack, chip8.stack);
+    assert_eq!((restored.delay_timer, restored.sound_timer), (30, 4));
+    assert_eq!((restored.audio_pattern, restored.pitch), (Some([0xAA; 16]), 80));
+    assert_eq!(restored.rpl_flags, chip8.rpl_flags);
+    assert_eq!(restored.keypad, chip8.keypad);
+    assert_eq!(restored.memory, chip8.memory);
+    assert_eq!(restored.display, chip8.display);
+    assert_eq!(restored.save_state(), data);
+
+    let mut target = Chip8::new();
+    target.quirks = chip8.quirks;
+    target.load_state(&data).unwrap();
+    assert_eq!(target.save_state(), data);
+}
+
+#[test]
+fn xo_memory_round_trips() {
+    let mut chip8 = Chip8::with_memory_size(XO_MEMORY_SIZE);
+    chip8.memory[0xFFFF] = 0x5A;
+    let restored = Chip8::from_state(&chip8.save_state()).unwrap();
+    assert_eq!(restored.memory.len(), XO_MEMORY_SIZE);
+    assert_eq!(restored.memory[0xFFFF], 0x5A);
+}
+
+#[test]
+fn load_state_rejects_other_version_quirks_and_memory_size() {
+    let chip8 = sample_state();
+    let data = chip8.save_state();
+
+    let mut other_version = data.clone();
+    other_version[VERSION_OFFSET] ^= 0x01;
+    let mut target = Chip8::new();
+    target.quirks = chip8.quirks;
+    let found = String::from_utf8(other_version[VERSION_OFFSET..][..EMULATOR_VERSION.len()].to_vec()).unwrap();
+    assert_eq!(target.load_state(&other_version), Err(SaveStateError::EmulatorVersionMismatch { found }));
+
+    let mut modern = Chip8::new();
+    assert_eq!(
+        modern.load_state(&data),
+        Err(SaveStateError::QuirksMismatch { found: chip8.quirks, expected: modern.quirks })
+    );
+    assert_eq!(modern.pc, 0x200, "a rejected state leaves the machine alone");
+
+    let mut xo = Chip8::with_memory_size(XO_MEMORY_SIZE);
+    xo.quirks = chip8.quirks;
+    assert_eq!(
+        xo.load_state(&data),
+        Err(SaveStateError::MemorySizeMismatch { found: 4096, expected: XO_MEMORY_SIZE })
+    );
+}
+
+#[test]
+fn corrupt_states_are_rejected() {
+    let data = sample_state().save_state();
+
+    let mut bad_magic = data.clone();
+    bad_magic[0] = b'X';
+    assert_eq!(Chip8::from_state(&bad_magic).err(), Some(SaveStateError::BadMagic));
+
+    let mut bad_format = data.clone();
+    bad_format[4] = 9;
+    assert_eq!(Chip8::from_state(&bad_format).err(), Some(SaveStateError::UnsupportedFormat { found: 9 }));
+
+    for len in 4..data.len() {
+        assert_eq!(Chip8::from_state(&data[..len]).err(), Some(SaveStateError::Truncated), "{len} bytes");
+    }
+
+    let mut trailing = data.clone();
+    trailing.push(0);
+    assert_eq!(Chip8::from_state(&trailing).err(), Some(SaveStateError::Corrupt("trailing data")));
+
+    let registers = VERSION_OFFSET + EMULATOR_VERSION.len() + 1;
+    let mut bad_pc = data.clone();
+    bad_pc[registers..registers + 2].copy_from_slice(&0x1000u16.to_le_bytes());
+    assert_eq!(Chip8::from_state(&bad_pc).err(), Some(SaveStateError::Corrupt("program counter outside memory")));
+
+    let mut bad_sp = data.clone();
+    bad_sp[registers + 4 + 16 + 32] = 17;
+    assert_eq!(Chip8::from_state(&bad_sp).err(), Some(SaveStateError::Corrupt("stack pointer out of range")));
+
+    let mut short_memory = Chip8::new();
+    short_memory.memory.truncate(2048);
+    assert_eq!(
+        Chip8::from_state(&short_memory.save_state()).err(),
+        Some(SaveStateError::Corrupt("memory size is neither 4 KiB nor 64 KiB"))
+    );
+}
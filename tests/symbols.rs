@@ -0,0 +1,97 @@
+use chip8_emulator::symbols::SymbolTable;
+
+fn table(text: &str) -> SymbolTable {
+    SymbolTable::parse(text).unwrap_or_else(|err| panic!("{err}\n{text}"))
+}
+
+fn parse_error(text: &str) -> String {
+    SymbolTable::parse(text).expect_err(text).to_string()
+}
+
+#[test]
+fn plain_files_need_prefixed_hex_addresses() {
+    let symbols = table("# game symbols\n0x200 main\n\n$2A8 draw_player ; sprite routine\n0X300\tdata\n");
+    assert_eq!(symbols.len(), 3);
+    assert_eq!(symbols.resolve("main"), Some(0x200));
+    assert_eq!(symbols.resolve("draw_player"), Some(0x2A8));
+    assert_eq!(symbols.name(0x300), Some("data"));
+
+    assert_eq!(parse_error("0x200 main\n512 other"), "line 2: invalid address '512' (use 0x or $ for hex)");
+    assert_eq!(parse_error("0xZZ main"), "line 1: invalid address '0xZZ' (use 0x or $ for hex)");
+    assert_eq!(parse_error("0x200"), "line 1: expected `address name`");
+    assert_eq!(parse_error("0x200 main extra"), "line 1: expected `address name`");
+    assert!(table("").is_empty());
+}
+
+#[test]
+fn octo_json_labels() {
+    let json = r#"{
+        "breakpoints": {},
+        "monitors": { "scores": { "base": 768, "length": 4 } },
+        "labels": { "main": 512, "draw \"fast\"": 680 },
+        "aliases": ["v0", 1.5, null]
+    }"#;
+    let symbols = table(json);
+    assert_eq!(symbols.len(), 2);
+    assert_eq!(symbols.name(0x200), Some("main"));
+    assert_eq!(symbols.name(0x2A8), Some("draw \"fast\""));
+
+    assert!(table("{}").is_empty());
+    assert_eq!(parse_error("{\n\"labels\": { \"big\": 70000 } }"), "line 2: address 70000 of 'big' is out of range");
+    assert_eq!(parse_error("{ \"labels\": { \"main\": 512 "), "line 1: expected '}'");
+    assert_eq!(parse_error("{ \"labels\": { \"main\": \"512\" } }"), "line 1: expected an integer address");
+}
+
+#[test]
+fn resolve_names_and_offsets() {
+    let symbols = table("0x200 main\n0x2A8 draw\n0xFFFE last");
+    assert_eq!(symbols.resolve(" draw "), Some(0x2A8));
+    assert_eq!(symbols.resolve("draw+0x4"), Some(0x2AC));
+    assert_eq!(symbols.resolve("draw + 10"), Some(0x2B8));
+    assert_eq!(symbols.resolve("last+2"), None);
+    assert_eq!(symbols.resolve("draw+zz"), None);
+    assert_eq!(symbols.resolve("missing"), None);
+}
+
+#[test]
+fn describe_uses_the_nearest_symbol_below() {
+    let symbols = table("0x200 main\n0x2A8 draw");
+    assert_eq!(symbols.describe(0x200).as_deref(), Some("main"));
+    assert_eq!(symbols.describe(0x2AA).as_deref(), Some("draw+0x2"));
+    assert_eq!(symbols.describe(0x2A6).as_deref(), Some("main+0xA6"));
+    assert_eq!(symbols.describe(0x1FE), None);
+    assert_eq!(symbols.format_address(0x1FE), "0x01FE");
+    assert_eq!(symbols.resolve(&symbols.format_address(0x2AA)), Some(0x2AA));
+    assert_eq!(symbols.disassemble(0x22AA), "CALL draw+0x2");
+    assert_eq!(symbols.disassemble(0x6001), "LD   V0, 0x01");
+}
+
+#[test]
+fn duplicate_labels_resolve_and_show_the_first_name() {
+    let symbols = table("0x200 start\n0x200 main\n0x210 loop\n0x220 loop");
+    assert_eq!(symbols.name(0x200), Some("start"));
+    assert_eq!(symbols.resolve("main"), Some(0x200));
+    assert_eq!(symbols.resolve("loop"), Some(0x220));
+    assert_eq!(symbols.name(0x210), None);
+    assert_eq!(symbols.describe(0x212).as_deref(), Some("start+0x12"));
+}
+
+#[test]
+fn later_tables_win() {
+    let mut symbols = table("0x200 main\n0x210 draw\n0x220 tick");
+    symbols.extend(table("0x200 entry\n0x230 draw\n0x220 step\n0x220 advance"));
+
+    assert_eq!(symbols.name(0x200), Some("entry"));
+    assert_eq!(symbols.resolve("main"), Some(0x200));
+    assert_eq!(symbols.name(0x220), Some("step"));
+    assert_eq!(symbols.resolve("tick"), Some(0x220));
+    assert_eq!(symbols.resolve("draw"), Some(0x230));
+    assert_eq!(symbols.name(0x230), Some("draw"));
+    assert_eq!(symbols.name(0x210), None);
+
+    for _ in 0..10 {
+        let mut again = table("0x200 b\n0x200 a\n0x200 c");
+        again.extend(table("0x300 x\n0x300 y"));
+        assert_eq!((again.name(0x200), again.name(0x300)), (Some("b"), Some("x")));
+    }
+}
@@ -0,0 +1,102 @@
+use std::cell::RefCell;
+use std::io::{self, Write};
+use std::rc::Rc;
+
+use chip8_emulator::chip8::Chip8;
+use chip8_emulator::trace::{self, TraceMode, Tracer};
+
+#[derive(Clone, Default)]
+struct SharedBuffer(Rc<RefCell<Vec<u8>>>);
+
+impl Write for SharedBuffer {
+    fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+        self.0.borrow_mut().write(buf)
+    }
+
+    fn flush(&mut self) -> io::Result<()> {
+        Ok(())
+    }
+}
+
+impl SharedBuffer {
+    fn lines(&self) -> Vec<String> {
+        String::from_utf8(self.0.borrow().clone()).unwrap().lines().map(str::to_string).collect()
+    }
+}
+
+// 0x200: ADD V0, 1; 0x202: ADD V1, 1; 0x204: ADD V2, 1; 0x206: JP 0x200
+const LOOP: [u8; 8] = [0x70, 0x01, 0x71, 0x01, 0x72, 0x01, 0x12, 0x00];
+
+fn run(tracer: &mut Tracer, chip8: &mut Chip8, cycles: usize) {
+    for _ in 0..cycles {
+        tracer.record(chip8);
+        chip8.emulate_cycle().unwrap();
+    }
+}
+
+fn pcs(lines: &[String]) -> Vec<&str> {
+    lines.iter().map(|line| &line[12..16]).collect()
+}
+
+#[test]
+fn range_filter_only_logs_matching_addresses() {
+    let buffer = SharedBuffer::default();
+    let mut tracer = Tracer::new(Box::new(buffer.clone()), TraceMode::Stream);
+    tracer.set_ranges(trace::parse_ranges("0x202, 205-206").unwrap());
+    let mut chip8 = Chip8::new();
+    chip8.load_rom_bytes(&LOOP).unwrap();
+
+    run(&mut tracer, &mut chip8, 8);
+    tracer.finish().unwrap();
+    let lines = buffer.lines();
+    assert_eq!(pcs(&lines), ["0202", "0206", "0202", "0206"]);
+    assert!(lines[0].starts_with("       1 PC=0202 OP=7101 V=01,00,"), "{}", lines[0]);
+    assert!(lines[3].starts_with("       7 PC=0206"), "{}", lines[3]);
+}
+
+#[test]
+fn parse_ranges_rejects_bad_input() {
+    assert_eq!(trace::parse_ranges("200-2FF,0x340"), Some(vec![0x200..=0x2FF, 0x340..=0x340]));
+    assert_eq!(trace::parse_ranges(""), Some(vec![]));
+    assert_eq!(trace::parse_ranges("300-200"), None);
+    assert_eq!(trace::parse_ranges("0x200-zz"), None);
+}
+
+#[test]
+fn ring_keeps_the_last_entries() {
+    let buffer = SharedBuffer::default();
+    let mut tracer = Tracer::new(Box::new(buffer.clone()), TraceMode::Ring(3));
+    let mut chip8 = Chip8::new();
+    chip8.load_rom_bytes(&LOOP).unwrap();
+
+    run(&mut tracer, &mut chip8, 10);
+    assert!(buffer.lines().is_empty(), "ring mode writes nothing until dumped");
+    tracer.dump().unwrap();
+    let lines = buffer.lines();
+    assert_eq!(pcs(&lines), ["0206", "0200", "0202"]);
+    assert!(lines[0].starts_with("       7 "));
+
+    run(&mut tracer, &mut chip8, 2);
+    tracer.finish().unwrap();
+    assert_eq!(pcs(&buffer.lines()[3..]), ["0204", "0206"]);
+}
+
+#[test]
+fn stalled_draw_is_logged_once() {
+    let buffer = SharedBuffer::default();
+    let mut tracer = Tracer::new(Box::new(buffer.clone()), TraceMode::Stream);
+    let mut chip8 = Chip8::new();
+    chip8.quirks.display_wait = true;
+    // 0x200: DRW V0, V0, 1; 0x202: JP 0x202
+    chip8.load_rom_bytes(&[0xD0, 0x01, 0x12, 0x02]).unwrap();
+
+    run(&mut tracer, &mut chip8, 5);
+    assert_eq!(chip8.pc, 0x200, "still waiting for the frame");
+    chip8.tick_timers();
+    run(&mut tracer, &mut chip8, 2);
+    tracer.finish().unwrap();
+
+    let lines = buffer.lines();
+    assert_eq!(pcs(&lines), ["0200", "0202"]);
+    assert!(lines[0].starts_with("       5 PC=0200 OP=D001"), "{}", lines[0]);
+}
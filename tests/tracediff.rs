@@ -0,0 +1,147 @@
+use std::cell::RefCell;
+use std::io::{self, Write};
+use std::rc::Rc;
+
+use chip8_emulator::chip8::Chip8;
+use chip8_emulator::clock::Clock;
+use chip8_emulator::headless::HeadlessRunner;
+use chip8_emulator::trace::{TraceMode, Tracer};
+use chip8_emulator::tracediff::{self, DiffOutcome, ReferenceState};
+
+#[derive(Clone, Default)]
+struct SharedBuffer(Rc<RefCell<Vec<u8>>>);
+
+impl Write for SharedBuffer {
+    fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+        self.0.borrow_mut().write(buf)
+    }
+
+    fn flush(&mut self) -> io::Result<()> {
+        Ok(())
+    }
+}
+
+fn parse(text: &str) -> ReferenceState {
+    ReferenceState::parse(7, text).unwrap().expect("a state line")
+}
+
+#[test]
+fn parse_reads_only_the_given_fields() {
+    let state = parse("PC=0x20A i=300 V3=1f");
+    assert_eq!(state.line, 7);
+    assert_eq!(state.pc, Some(0x20A));
+    assert_eq!(state.i, Some(0x300));
+    assert_eq!(state.v[3], Some(0x1F));
+    assert!(state.v.iter().enumerate().all(|(register, value)| register == 3 || value.is_none()));
+    assert_eq!((state.opcode, state.sp, state.delay_timer, state.sound_timer), (None, None, None, None));
+    assert!(state.memory.is_empty());
+}
+
+#[test]
+fn parse_reads_full_trace_lines() {
+    let text = "      12 PC=0206 OP=D015 V=05,0A,00,00,00,00,00,00,00,00,00,00,00,00,00,FF I=0300 SP=01 DT=3C ST=00 ; DRW  V0, V1, 5";
+    let state = parse(text);
+    assert_eq!(state.text, text.trim());
+    assert_eq!((state.pc, state.opcode), (Some(0x206), Some(0xD015)));
+    assert_eq!(state.v[0x0], Some(0x05));
+    assert_eq!(state.v[0x1], Some(0x0A));
+    assert_eq!(state.v[0xF], Some(0xFF));
+    assert_eq!((state.i, state.sp, state.delay_timer, state.sound_timer), (Some(0x300), Some(1), Some(0x3C), Some(0)));
+}
+
+#[test]
+fn parse_reads_memory_fields() {
+    let state = parse("PC=200 M[0300]=0A0b0C m[0x400]=FF");
+    assert_eq!(state.memory, [(0x300, vec![0x0A, 0x0B, 0x0C]), (0x400, vec![0xFF])]);
+}
+
+#[test]
+fn parse_skips_comments_and_blank_lines() {
+    for text in ["", "   ", "# header", "; PC=0200", "   # PC=0200"] {
+        assert_eq!(ReferenceState::parse(1, text).unwrap(), None, "{text:?}");
+    }
+    assert_eq!(parse("PC=0200 ; PC=0300 junk").pc, Some(0x200));
+
+    let log = "# reference\nPC=0200\n\nPC=0202 ; after\n";
+    let states = ReferenceState::parse_log(log).unwrap();
+    assert_eq!(states.iter().map(|state| (state.line, state.pc)).collect::<Vec<_>>(), [(2, Some(0x200)), (4, Some(0x202))]);
+}
+
+#[test]
+fn parse_rejects_malformed_lines() {
+    for (text, message) in [
+        ("PC=0200 junk", "expected `KEY=value`, found 'junk'"),
+        ("12 13 PC=0200", "expected `KEY=value`, found '13'"),
+        ("PC=zz", "invalid value 'zz' for PC"),
+        ("SP=100", "invalid value '100' for SP"),
+        ("V=01,02", "V needs 16 comma-separated values"),
+        ("VG=01", "unknown field 'VG'"),
+        ("V1=100", "invalid value '100' for V1"),
+        ("M[zz]=01", "invalid address in M[ZZ]"),
+        ("M[0300]=ABC", "invalid value 'ABC' for M[0300]"),
+        ("M[0300]=", "invalid value '' for M[0300]"),
+        ("XY=1", "unknown field 'XY'"),
+    ] {
+        let err = ReferenceState::parse(3, text).unwrap_err();
+        assert_eq!((err.line, err.message.as_str()), (3, message), "{text}");
+    }
+    assert_eq!(ReferenceState::parse_log("PC=0200\nPC=").unwrap_err().line, 2);
+}
+
+#[test]
+fn matches_own_trace_with_random_numbers_and_display_wait() {
+    // 0x200: RND V0, 0xFF; 0x202: RND V1, 0x3F; 0x204: LD F, V0; 0x206: DRW V0, V1, 5
+    // 0x208: ADD V0, V1; 0x20A: JP 0x200
+    let rom = [0xC0, 0xFF, 0xC1, 0x3F, 0xF0, 0x29, 0xD0, 0x15, 0x80, 0x14, 0x12, 0x00];
+    let machine = |seed| {
+        let mut chip8 = Chip8::new();
+        chip8.quirks.display_wait = true;
+        chip8.load_rom_bytes(&rom).unwrap();
+        chip8.seed_rng(seed);
+        chip8
+    };
+
+    let buffer = SharedBuffer::default();
+    let mut runner = HeadlessRunner::new(machine(1));
+    runner.tracer = Some(Tracer::new(Box::new(buffer.clone()), TraceMode::Stream));
+    runner.run_frames(5).unwrap();
+    runner.tracer.take().unwrap().finish().unwrap();
+
+    let log = String::from_utf8(buffer.0.borrow().clone()).unwrap();
+    let reference = ReferenceState::parse_log(&log).unwrap();
+    // The first frame stalls at DXYN, every later one runs the loop once.
+    assert_eq!(reference.len(), 3 + 4 * 6, "{log}");
+
+    let mut chip8 = machine(2);
+    match tracediff::diff_trace(&mut chip8, &mut Clock::default(), reference, 4) {
+        DiffOutcome::Matched { instructions } => assert_eq!(instructions, 27),
+        DiffOutcome::Diverged(divergence) => panic!("diverged at line {}: {:?}", divergence.reference.line, divergence.mismatches),
+    }
+    assert_eq!(chip8.display, runner.chip8.display);
+}
+
+#[test]
+fn reports_first_divergence_with_history() {
+    let mut chip8 = Chip8::new();
+    chip8.load_rom_bytes(&[0x60, 0x01, 0x61, 0x02, 0x62, 0x03]).unwrap();
+    let reference = ReferenceState::parse_log("PC=0200\nPC=0202 V0=01\nPC=0204 V1=03\nPC=0206\n").unwrap();
+
+    let DiffOutcome::Diverged(divergence) = tracediff::diff_trace(&mut chip8, &mut Clock::default(), reference, 1) else {
+        panic!("expected a divergence");
+    };
+    assert_eq!(divergence.reference.line, 3);
+    assert_eq!(divergence.actual.pc, 0x204);
+    assert_eq!(divergence.mismatches.len(), 1);
+    assert_eq!(divergence.mismatches[0].field, "V1");
+    assert_eq!((divergence.mismatches[0].expected.as_str(), divergence.mismatches[0].actual.as_str()), ("0x03", "0x02"));
+    assert_eq!(divergence.history.iter().map(|entry| entry.pc).collect::<Vec<_>>(), [0x202]);
+    assert!(divergence.fault.is_none());
+}
+
+#[test]
+#[should_panic(expected = "needs a clock that runs instructions")]
+fn zero_instructions_per_second_is_rejected() {
+    let mut chip8 = Chip8::new();
+    let reference = ReferenceState::parse_log("PC=0x200").unwrap();
+    tracediff::diff_trace(&mut chip8, &mut Clock::new(0), reference, 0);
+}